@@ -1,74 +1,421 @@
 use anyhow::{anyhow, Context, Ok, Result};
+use std::io::Write;
 use std::process::{Command, Stdio};
 
+use crate::custom_action::CustomAction;
+use crate::escalation::{execute_privileged, is_authentication_required, EscalationBackend};
 use crate::logs::log;
+use crate::scheduling::{IoClass, IoPriority, SchedPolicy};
+use crate::signals::{KillError, ProcessHandle};
+use crate::systemd::UnitCommand;
 
 #[derive(Debug, Clone)]
 pub struct MenuAction {
-    pub name: &'static str,
+    pub name: String,
     pub operation: Operation,
+    pub confirm: ConfirmPolicy,
+}
+
+impl MenuAction {
+    /// Name without the command, e.g. "Kill forcefully" or "Change priority", used as a key in the config
+    pub fn short_name(&self) -> &str {
+        let name = self.name.split(": ").next().unwrap_or(&self.name);
+        name.trim_end_matches('…')
+    }
 }
 
 #[derive(Debug, Clone)]
 pub enum Operation {
-    KillSignal { template: &'static str },
+    KillSignal { signal: i32, superuser: bool },
+    PickSignal { superuser: bool },
+    TerminateThenKill { superuser: bool },
+    Restart,
+    KillTree { signal: i32, superuser: bool },
+    TogglePause,
+    Renice,
+    ChangeScheduler,
+    EditAffinity,
+    ChangeIoPriority,
+    ChangeOomScoreAdj,
     ShowDetails,
+    SystemdUnit(UnitCommand),
+    FreezeCgroup,
+    KillCgroup,
+    Custom(CustomAction),
+}
+
+impl Operation {
+    /// Whether a signal, value or order is picked in another window before anything is executed
+    pub fn needs_pick(&self) -> bool {
+        matches!(
+            self,
+            Operation::PickSignal { .. }
+                | Operation::KillTree { .. }
+                | Operation::Renice
+                | Operation::ChangeScheduler
+                | Operation::ChangeIoPriority
+                | Operation::ChangeOomScoreAdj
+                | Operation::EditAffinity
+        )
+    }
+
+    /// Whether the action may ask for confirmation, the others only show something or open a window to pick from
+    pub fn is_confirmable(&self) -> bool {
+        !self.needs_pick() && !matches!(self, Operation::ShowDetails)
+    }
+}
+
+/// Value chosen from a list of options to adjust a process with
+#[derive(Debug, Clone)]
+pub enum PickValue {
+    Nice(i32),
+    Scheduler { policy: SchedPolicy, priority: i32 },
+    IoPriority(IoPriority),
+    OomScoreAdj(i32),
+}
+
+impl PickValue {
+    pub fn describe(&self) -> String {
+        match self {
+            PickValue::Nice(nice) => format!("nice value {}", nice),
+            PickValue::Scheduler { policy, priority } => match policy.is_realtime() {
+                true => format!("scheduling policy {} {}", policy.name(), priority),
+                false => format!("scheduling policy {}", policy.name()),
+            },
+            PickValue::IoPriority(priority) => format!("I/O priority {}", priority.describe(0)),
+            PickValue::OomScoreAdj(score_adj) => format!("OOM score adjustment {}", score_adj),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PickOption {
+    pub label: String,
+    pub value: PickValue,
+}
+
+impl PickOption {
+    fn new(label: &str, value: PickValue) -> Self {
+        PickOption {
+            label: label.to_string(),
+            value,
+        }
+    }
+}
+
+/// When to ask for confirmation before an action sends signals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfirmPolicy {
+    Always,
+    Destructive, // only for SIGKILL and Superuser actions
+    Never,
+}
+
+impl ConfirmPolicy {
+    pub fn parse(value: &str) -> Option<ConfirmPolicy> {
+        match value {
+            "always" => Some(ConfirmPolicy::Always),
+            "destructive" => Some(ConfirmPolicy::Destructive),
+            "never" => Some(ConfirmPolicy::Never),
+            _ => None,
+        }
+    }
+
+    pub fn requires_confirmation(&self, destructive: bool) -> bool {
+        match self {
+            ConfirmPolicy::Always => true,
+            ConfirmPolicy::Destructive => destructive,
+            ConfirmPolicy::Never => false,
+        }
+    }
 }
 
-pub fn generate_known_menu_actions() -> Vec<MenuAction> {
+pub fn generate_known_menu_actions(escalation: EscalationBackend) -> Vec<MenuAction> {
+    let su = escalation.program();
     vec![
         MenuAction {
-            name: "Process details",
+            name: "Process details".to_string(),
             operation: Operation::ShowDetails,
+            confirm: ConfirmPolicy::Never,
+        },
+        MenuAction {
+            name: "Interrupt: kill -2".to_string(),
+            operation: Operation::KillSignal {
+                signal: libc::SIGINT,
+                superuser: false,
+            },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Terminate gracefully: kill -15".to_string(),
+            operation: Operation::KillSignal {
+                signal: libc::SIGTERM,
+                superuser: false,
+            },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Kill forcefully: kill -9".to_string(),
+            operation: Operation::KillSignal {
+                signal: libc::SIGKILL,
+                superuser: false,
+            },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Terminate, then kill if it survives: kill -15, kill -9".to_string(),
+            operation: Operation::TerminateThenKill { superuser: false },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Restart (same command, cwd, env): kill -15, exec".to_string(),
+            operation: Operation::Restart,
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Pause / Resume: kill -STOP / kill -CONT".to_string(),
+            operation: Operation::TogglePause,
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Change priority…: renice".to_string(),
+            operation: Operation::Renice,
+            confirm: ConfirmPolicy::Never,
+        },
+        MenuAction {
+            name: "Change scheduling policy…: chrt".to_string(),
+            operation: Operation::ChangeScheduler,
+            confirm: ConfirmPolicy::Never,
         },
         MenuAction {
-            name: "Interrupt: kill -2",
-            operation: Operation::KillSignal { template: "kill -2 " },
+            name: "Change I/O priority…: ionice".to_string(),
+            operation: Operation::ChangeIoPriority,
+            confirm: ConfirmPolicy::Never,
         },
         MenuAction {
-            name: "Terminate gracefully: kill -15",
-            operation: Operation::KillSignal { template: "kill -15 " },
+            name: "Change OOM score adjustment…: choom".to_string(),
+            operation: Operation::ChangeOomScoreAdj,
+            confirm: ConfirmPolicy::Never,
         },
         MenuAction {
-            name: "Kill forcefully: kill -9",
-            operation: Operation::KillSignal { template: "kill -9 " },
+            name: "Change CPU affinity…: taskset".to_string(),
+            operation: Operation::EditAffinity,
+            confirm: ConfirmPolicy::Never,
         },
         MenuAction {
-            name: "Superuser Terminate: sudo kill -15",
+            name: "Terminate process tree…: kill -15".to_string(),
+            operation: Operation::KillTree {
+                signal: libc::SIGTERM,
+                superuser: false,
+            },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Kill process tree…: kill -9".to_string(),
+            operation: Operation::KillTree {
+                signal: libc::SIGKILL,
+                superuser: false,
+            },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Send signal…: kill -N".to_string(),
+            operation: Operation::PickSignal { superuser: false },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Freeze / Thaw whole cgroup: cgroup.freeze".to_string(),
+            operation: Operation::FreezeCgroup,
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Kill whole cgroup: cgroup.kill".to_string(),
+            operation: Operation::KillCgroup,
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Stop systemd unit: systemctl stop".to_string(),
+            operation: Operation::SystemdUnit(UnitCommand::Stop),
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Restart systemd unit: systemctl restart".to_string(),
+            operation: Operation::SystemdUnit(UnitCommand::Restart),
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: "Terminate all processes of systemd unit: systemctl kill".to_string(),
+            operation: Operation::SystemdUnit(UnitCommand::Kill),
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: format!("Superuser Terminate: {su} kill -15"),
             operation: Operation::KillSignal {
-                template: "sudo kill -15 ",
+                signal: libc::SIGTERM,
+                superuser: true,
             },
+            confirm: ConfirmPolicy::Destructive,
         },
         MenuAction {
-            name: "Superuser Kill: sudo kill -9",
+            name: format!("Superuser Kill: {su} kill -9"),
             operation: Operation::KillSignal {
-                template: "sudo kill -9 ",
+                signal: libc::SIGKILL,
+                superuser: true,
+            },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: format!("Superuser Terminate, then kill: {su} kill -15, {su} kill -9"),
+            operation: Operation::TerminateThenKill { superuser: true },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: format!("Superuser Kill process tree…: {su} kill -9"),
+            operation: Operation::KillTree {
+                signal: libc::SIGKILL,
+                superuser: true,
             },
+            confirm: ConfirmPolicy::Destructive,
+        },
+        MenuAction {
+            name: format!("Superuser Send signal…: {su} kill -N"),
+            operation: Operation::PickSignal { superuser: true },
+            confirm: ConfirmPolicy::Destructive,
         },
     ]
 }
 
-pub fn kill_pid(pid: &String, command_template: &'static str) -> Result<()> {
-    let cmd = format!("{command_template}{pid}");
-    execute_shell(cmd)
+pub fn nice_pick_options() -> Vec<PickOption> {
+    vec![
+        PickOption::new("-20: highest priority", PickValue::Nice(-20)),
+        PickOption::new("-10: high priority", PickValue::Nice(-10)),
+        PickOption::new(" -5: raised priority", PickValue::Nice(-5)),
+        PickOption::new("  0: default priority", PickValue::Nice(0)),
+        PickOption::new("  5: lowered priority", PickValue::Nice(5)),
+        PickOption::new(" 10: low priority", PickValue::Nice(10)),
+        PickOption::new(" 19: lowest priority", PickValue::Nice(19)),
+    ]
+}
+
+pub fn oom_score_adj_pick_options() -> Vec<PickOption> {
+    vec![
+        PickOption::new("-1000: never kill on out-of-memory", PickValue::OomScoreAdj(-1000)),
+        PickOption::new(" -500: strongly protect", PickValue::OomScoreAdj(-500)),
+        PickOption::new(" -100: slightly protect", PickValue::OomScoreAdj(-100)),
+        PickOption::new("    0: default", PickValue::OomScoreAdj(0)),
+        PickOption::new("  100: slightly prefer", PickValue::OomScoreAdj(100)),
+        PickOption::new("  500: strongly prefer", PickValue::OomScoreAdj(500)),
+        PickOption::new(" 1000: kill first on out-of-memory", PickValue::OomScoreAdj(1000)),
+    ]
+}
+
+pub fn scheduler_pick_options() -> Vec<PickOption> {
+    let option = |label: &str, policy: SchedPolicy, priority: i32| {
+        PickOption::new(label, PickValue::Scheduler { policy, priority })
+    };
+    vec![
+        option("SCHED_OTHER: default time-sharing", SchedPolicy::Other, 0),
+        option("SCHED_BATCH: CPU-intensive batch job", SchedPolicy::Batch, 0),
+        option("SCHED_IDLE: run only when the CPU is idle", SchedPolicy::Idle, 0),
+        option("SCHED_FIFO 1: real-time, lowest priority", SchedPolicy::Fifo, 1),
+        option("SCHED_FIFO 50: real-time, medium priority", SchedPolicy::Fifo, 50),
+        option("SCHED_FIFO 99: real-time, highest priority", SchedPolicy::Fifo, 99),
+        option(
+            "SCHED_RR 1: real-time round-robin, lowest priority",
+            SchedPolicy::RoundRobin,
+            1,
+        ),
+        option(
+            "SCHED_RR 50: real-time round-robin, medium priority",
+            SchedPolicy::RoundRobin,
+            50,
+        ),
+        option(
+            "SCHED_RR 99: real-time round-robin, highest priority",
+            SchedPolicy::RoundRobin,
+            99,
+        ),
+    ]
+}
+
+pub fn io_priority_pick_options() -> Vec<PickOption> {
+    let option = |label: &str, class: IoClass, level: i32| {
+        PickOption::new(label, PickValue::IoPriority(IoPriority { class, level }))
+    };
+    vec![
+        option("Idle: use disk only when nobody else does", IoClass::Idle, 0),
+        option("Best-effort 7: lowest priority", IoClass::BestEffort, 7),
+        option("Best-effort 4: default priority", IoClass::BestEffort, 4),
+        option("Best-effort 0: highest priority", IoClass::BestEffort, 0),
+        option("Realtime 7: lowest real-time priority", IoClass::Realtime, 7),
+        option("Realtime 4: medium real-time priority", IoClass::Realtime, 4),
+        option("Realtime 0: highest real-time priority", IoClass::Realtime, 0),
+    ]
+}
+
+/// Whether a signal can't be handled gracefully by the process or runs with elevated privileges
+pub fn is_destructive(signal: i32, superuser: bool) -> bool {
+    signal == libc::SIGKILL || superuser
+}
+
+/// Send a signal to the process seen at the given start time, unless its PID has been reused since then
+pub fn kill_pid(pid: &str, start_time: Option<u64>, signal: i32, superuser: bool) -> Result<(), KillError> {
+    let handle = ProcessHandle::open(pid, start_time)?;
+    kill_process(&handle, signal, superuser)
 }
 
-pub fn execute_shell(cmd: String) -> Result<()> {
+/// Send a signal natively. Superuser actions only spawn a privileged `kill` when the direct attempt is not permitted.
+pub fn kill_process(handle: &ProcessHandle, signal: i32, superuser: bool) -> Result<(), KillError> {
+    match handle.send_signal(signal) {
+        Err(KillError::PermissionDenied) if superuser => {
+            if !handle.is_alive() {
+                return Err(KillError::NoSuchProcess); // don't let `kill` hit a process that reused the PID
+            }
+            let pid = &handle.pid;
+            execute_privileged(format!("kill -{signal} {pid}")).map_err(|error| {
+                match is_authentication_required(&error) {
+                    true => KillError::AuthenticationRequired,
+                    false => KillError::EscalationFailed(error),
+                }
+            })
+        }
+        result => result,
+    }
+}
+
+/// Run a shell command and return its standard output
+pub fn execute_shell_output(cmd: String) -> Result<String> {
     log(format!("Executing command: {:?}", cmd).as_str());
-    let c = Command::new("sh")
-        .arg("-c")
-        .arg(cmd.clone())
-        .stdin(Stdio::inherit())
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(cmd);
+    run_command(command, None, false)
+}
+
+/// Run a command and return its standard output, failing with its output if it exits unsuccessfully.
+/// Standard input gets the given data, or the terminal when it's handed over to the command, e.g. for a password prompt.
+pub fn run_command(mut command: Command, stdin_data: Option<String>, inherit_terminal: bool) -> Result<String> {
+    let stdin = match (&stdin_data, inherit_terminal) {
+        (Some(_), _) => Stdio::piped(),
+        (None, true) => Stdio::inherit(),
+        (None, false) => Stdio::null(),
+    };
+    let mut child = command
+        .stdin(stdin)
         .stderr(Stdio::piped())
         .stdout(Stdio::piped())
         .spawn()
         .context("failed to start a command")?;
-    let output = c.wait_with_output().context("failed to read command output")?;
+    if let (Some(data), Some(mut stdin)) = (stdin_data, child.stdin.take()) {
+        stdin
+            .write_all(data.as_bytes())
+            .context("failed to write to the standard input of a command")?;
+    }
+    let output = child.wait_with_output().context("failed to read command output")?;
 
     if !output.status.success() {
         let error = format!(
             "Failed to execute command: {:?}, {}\n{}\n{}",
-            cmd,
+            command,
             output.status,
             String::from_utf8_lossy(&output.stderr),
             String::from_utf8_lossy(&output.stdout),
@@ -76,5 +423,5 @@ pub fn execute_shell(cmd: String) -> Result<()> {
         log(error.as_str());
         return Err(anyhow!(error));
     }
-    Ok(())
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
 }
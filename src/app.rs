@@ -1,13 +1,27 @@
 use anyhow::Result;
 use ratatui::widgets::TableState;
 use signal_hook::{consts::SIGINT, consts::SIGTERM, iterator::Signals};
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
 use std::sync::mpsc;
 use std::thread;
+use std::time::Duration;
 use sysinfo::{System, SystemExt};
 
-use crate::action_menu::{generate_known_menu_actions, MenuAction};
-use crate::appdata::{Ordering, WindowFocus};
+use crate::action_menu::{generate_known_menu_actions, MenuAction, PickOption};
+use crate::appdata::{ExtraColumn, Ordering, TreeKillOrder, WindowFocus};
+use crate::audit::AuditLog;
+use crate::cli::CliArgs;
+use crate::config::Config;
+use crate::escalation;
+use crate::event::Event;
 use crate::logs::print_logs;
+use crate::path_holders::PathHolders;
+use crate::ports::PortOwners;
+use crate::process_tree::TreeNode;
+use crate::protection::ProtectionRules;
+use crate::restart::ProcessSnapshot;
+use crate::signals::{linux_signals, SignalInfo};
 use crate::sysinfo::{ProcessStat, SystemProcStats, SystemStat};
 use crate::tui::Tui;
 
@@ -23,8 +37,44 @@ pub struct App {
     pub init_stat: SystemStat,
     pub filter_text: String,
     pub filtered_processes: Vec<ProcessStat>,
+    pub marked_pids: HashSet<String>,
     pub menu_action_cursor: usize,
     pub known_menu_actions: Vec<MenuAction>,
+    pub signal_catalogue: Vec<SignalInfo>,
+    pub signal_catalogue_cursor: usize,
+    pub signal_catalogue_superuser: bool,
+    pub tree_kill_nodes: Vec<TreeNode>,
+    pub tree_kill_signal: i32,
+    pub tree_kill_superuser: bool,
+    pub tree_kill_order: TreeKillOrder,
+    pub tree_kill_scroll: usize,
+    pub kill_confirm_origin: WindowFocus,
+    pub kill_confirm_title: String,
+    pub kill_confirm_message: String,
+    pub kill_confirm_scroll: usize,
+    pub option_pick_title: String,
+    pub option_pick_items: Vec<PickOption>,
+    pub option_pick_cursor: usize,
+    pub extra_columns: Vec<ExtraColumn>,
+    pub column_pick_cursor: usize,
+    pub affinity_cores: Vec<bool>,
+    pub affinity_all_threads: bool,
+    pub affinity_group_members: bool,
+    pub affinity_cursor: usize,
+    pub password_input: String,
+    pub password_error: Option<String>,
+    pub escalation_origin: WindowFocus, // window whose action is retried after authentication
+    pub terminal_escalation_pending: bool,
+    pub filter_note: Option<String>, // remark about the port lookup shown under the filter
+    pub port_owners_cache: HashMap<u16, PortOwners>, // lookups of filtered ports, until the next refresh
+    pub path_holders_cache: HashMap<PathBuf, PathHolders>, // scans of filtered paths, until the next refresh
+    pub protection_rules: ProtectionRules,
+    pub protection_confirmed: Vec<String>, // protected PIDs the user typed the name of, until the next refresh
+    pub protected_confirm_name: String,    // process name to type to confirm
+    pub protected_confirm_message: String,
+    pub protected_confirm_input: String,
+    pub protected_confirm_pids: Vec<String>,
+    pub protected_confirm_origin: WindowFocus,
     pub proc_list_table_state: TableState,
     pub horizontal_scroll: i32,
     pub sysinfo_scroll: i32,
@@ -35,15 +85,31 @@ pub struct App {
     pub info_lines_num: Option<usize>,
     pub info_message_scroll: usize,
     pub group_by_exe: bool,
+    pub grace_period: Duration,
+    pub dry_run: bool,
+    pub event_sender: Option<mpsc::Sender<Event>>,
+    pub kill_sequence_log: Vec<String>,
+    pub audit_log: AuditLog,
+    pub pending_restarts: Vec<(String, ProcessSnapshot)>, // processes to relaunch once the kill sequence ends
 }
 
 impl App {
-    pub fn new() -> Self {
-        Self {
-            known_menu_actions: generate_known_menu_actions(),
+    pub fn new(args: CliArgs, config: &Config) -> Result<Self> {
+        let escalation = config.escalation_backend()?;
+        escalation::set_backend(escalation);
+        let mut known_menu_actions = generate_known_menu_actions(escalation);
+        config.apply_confirm_policies(&mut known_menu_actions, args.confirm)?;
+        known_menu_actions.extend(config.custom_menu_actions(escalation, args.confirm)?);
+        Ok(Self {
+            grace_period: args.grace_period,
+            dry_run: args.dry_run,
+            known_menu_actions,
+            signal_catalogue: linux_signals(),
+            protection_rules: config.protection_rules(),
+            audit_log: AuditLog::new(config.audit_log.clone()),
             sysinfo_sys: System::new_all(),
             ..Default::default()
-        }
+        })
     }
 
     pub fn run(&mut self) -> Result<()> {
@@ -52,11 +118,17 @@ impl App {
         self.refresh_processes();
         self.init_stat = self.sys_stat.clone();
         let mut tui = Tui::new();
+        self.event_sender = Some(tui.event_handler.sender());
         tui.enter()?;
 
         while !self.should_quit {
             tui.draw(self)?;
             tui.handle_events(self)?;
+            if self.terminal_escalation_pending {
+                tui.exit()?;
+                self.escalate_in_terminal();
+                tui.resume()?;
+            }
 
             signal_rx.try_recv().ok().map(|_| {
                 self.quit();
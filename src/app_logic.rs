@@ -1,23 +1,59 @@
+use anyhow::{anyhow, Result};
+use itertools::Itertools;
 use ratatui::text::Line;
 use std::cmp::Ordering::Equal;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
 
-use crate::action_menu::{kill_pid, MenuAction, Operation};
+use crate::action_menu::{
+    io_priority_pick_options, is_destructive, kill_pid, nice_pick_options, oom_score_adj_pick_options,
+    scheduler_pick_options, MenuAction, Operation, PickOption, PickValue,
+};
 use crate::app::App;
-use crate::appdata::{Ordering, WindowFocus};
-use crate::numbers::{ClampNumExt, MyIntExt};
-use crate::strings::contains_all_words;
+use crate::appdata::{ExtraColumn, Ordering, TreeKillOrder, WindowFocus};
+use crate::cgroup::Cgroup;
+use crate::custom_action::{render_template, CustomAction};
+use crate::escalation::{
+    self, describe_privileged, execute_privileged, is_authentication_required, validate_password, AuthMode,
+};
+use crate::event::Event;
+use crate::kill_sequence::{spawn_kill_sequence, spawn_tree_kill, KillOutcome, KillProgress, KILL_WAIT};
+use crate::logs::log;
+use crate::numbers::{ClampNumExt, MyIntExt, PercentFormatterExt};
+use crate::oom::{oom_score_adj_command, set_oom_score_adj};
+use crate::path_holders::{find_path_holders, parse_path_word};
+use crate::ports::{find_port_owners, parse_port_word};
+use crate::process_tree::{children_first_levels, children_first_pids, collect_subtree};
+use crate::protection::{is_harmless_signal, Protection};
+use crate::restart::ProcessSnapshot;
+use crate::scheduling::{
+    chrt_command, get_affinity, ionice_command, process_tasks, renice_command, set_affinity, set_io_priority, set_nice,
+    set_scheduler, taskset_command,
+};
+use crate::signals::{ends_process, signal_name, KillError};
+use crate::strings::{contains_all_words, shell_quote};
 use crate::sysinfo::{get_proc_stats, get_system_stats, group_by_exe_path, ProcessStat};
+use crate::systemd::{run_unit_command, unit_command_line, SystemdUnit, UnitCommand};
 
 const HELP_INFO: &str = "Keyboard controls:
 `?` to show help.
 `Ctrl+F` or `F` to filter processes.
+`:` to find processes listening on a port, e.g. `:8080`.
+`@` to find processes keeping a file, directory or mount point busy, e.g. `@/mnt/usb`.
 Arrows `↑` and `↓` to navigate list.
 `F5` or `R` to refresh list.
 `S` to sort.
 `M` to order by memory usage.
 `C` to order by CPU usage.
 `U` to order by uptime.
+`Shift+O` to order by OOM score.
+`Shift+I` to order by disk I/O rate.
+`H` to show the history of executed actions.
 `G` group processes by executable path.
+`Space` to mark a process, actions apply to all marked processes.
+`P` to pause a process with SIGSTOP or resume a stopped one with SIGCONT.
+`V` to choose visible columns.
 `Enter` to select or confirm.
 `Tab` to switch tab.
 `Esc` to cancel or quit.";
@@ -27,10 +63,20 @@ impl App {
         self.sys_stat = get_system_stats(&mut self.sysinfo_sys);
     }
 
+    /// Memory breakdown is read only for the columns showing it and for summing up groups by PSS
+    fn needs_memory_breakdown(&self) -> bool {
+        self.group_by_exe || self.extra_columns.iter().any(|it| it.needs_memory_breakdown())
+    }
+
     pub fn refresh_processes(&mut self) {
         self.previous_proc_stats = self.proc_stats.clone();
-        self.proc_stats = get_proc_stats(&self.sys_stat.memory, &mut self.sysinfo_sys);
+        let memory_breakdown = self.needs_memory_breakdown();
+        self.proc_stats = get_proc_stats(&self.sys_stat.memory, &mut self.sysinfo_sys, memory_breakdown);
         self.enrich_proc_stats();
+        self.prune_marks();
+        self.protection_confirmed.clear();
+        self.port_owners_cache.clear();
+        self.path_holders_cache.clear();
         self.filter_processes();
     }
 
@@ -51,9 +97,41 @@ impl App {
                     .clamp_max(self.known_menu_actions.len() as i32 - 1)
                     .clamp_usize();
             }
+            WindowFocus::SignalCatalogue => {
+                self.signal_catalogue_cursor = (self.signal_catalogue_cursor as i32 + delta)
+                    .clamp_max(self.signal_catalogue.len() as i32 - 1)
+                    .clamp_usize();
+            }
+            WindowFocus::OptionPick => {
+                self.option_pick_cursor = (self.option_pick_cursor as i32 + delta)
+                    .clamp_max(self.option_pick_items.len() as i32 - 1)
+                    .clamp_usize();
+            }
+            WindowFocus::ColumnPick => {
+                self.column_pick_cursor = (self.column_pick_cursor as i32 + delta)
+                    .clamp_max(ExtraColumn::all().len() as i32 - 1)
+                    .clamp_usize();
+            }
+            WindowFocus::AffinityPick => {
+                self.affinity_cursor = (self.affinity_cursor as i32 + delta)
+                    .clamp_max(self.affinity_cores.len() as i32 + 1) // 2 options after the cores
+                    .clamp_usize();
+            }
+            WindowFocus::KillConfirm => {
+                let lines_count = self.kill_confirm_message.lines().count() as i32;
+                self.kill_confirm_scroll = (self.kill_confirm_scroll as i32 + delta)
+                    .clamp_max(lines_count - 1)
+                    .clamp_usize();
+            }
+            WindowFocus::TreeKillPreview => {
+                self.tree_kill_scroll = (self.tree_kill_scroll as i32 + delta)
+                    .clamp_max(self.tree_kill_nodes.len() as i32 - 1)
+                    .clamp_usize();
+            }
             WindowFocus::SystemStats => {
                 self.sysinfo_scroll = (self.sysinfo_scroll + delta).clamp_min(0);
             }
+            WindowFocus::PasswordPrompt | WindowFocus::ProtectedConfirm => {}
         }
     }
 
@@ -78,28 +156,58 @@ impl App {
         self.ordering = match self.ordering {
             Ordering::ByUptime => Ordering::ByMemory,
             Ordering::ByMemory => Ordering::ByCpu,
-            Ordering::ByCpu => Ordering::ByUptime,
+            Ordering::ByCpu if self.extra_columns.contains(&ExtraColumn::OomScore) => Ordering::ByOomScore,
+            Ordering::ByCpu | Ordering::ByOomScore if self.extra_columns.contains(&ExtraColumn::DiskIo) => {
+                Ordering::ByDiskIo
+            }
+            Ordering::ByCpu | Ordering::ByOomScore | Ordering::ByDiskIo => Ordering::ByUptime,
         };
         self.filter_processes();
     }
 
     pub fn set_process_ordering(&mut self, ordering: Ordering) {
+        let sorted_column = ExtraColumn::all()
+            .into_iter()
+            .find(|it| it.ordering().as_ref() == Some(&ordering));
+        if let Some(column) = sorted_column.filter(|it| !self.extra_columns.contains(it)) {
+            self.extra_columns.push(column);
+            self.extra_columns
+                .sort_by_key(|it| ExtraColumn::all().iter().position(|c| c == it));
+        }
         self.ordering = ordering;
         self.filter_processes();
     }
 
     pub fn filter_processes(&mut self) {
-        let filter_words: Vec<String> = self
-            .filter_text
-            .split_whitespace()
+        let words: Vec<&str> = self.filter_text.split_whitespace().collect();
+        let ports: Vec<u16> = words.iter().filter_map(|it| parse_port_word(it)).collect();
+        let paths: Vec<PathBuf> = words.iter().filter_map(|it| parse_path_word(it)).collect();
+        let filter_words: Vec<String> = words
+            .iter()
+            .filter(|it| parse_port_word(it).is_none() && parse_path_word(it).is_none())
             .map(|it| it.to_lowercase())
             .collect();
+        self.filter_note = None;
+        let port_pids = self.find_port_pids(&ports);
+        let path_reasons = self.find_path_reasons(&paths);
         self.filtered_processes = self
             .proc_stats
             .processes
             .iter()
             .filter(|it: &&ProcessStat| contains_all_words(it.search_name().as_str(), &filter_words))
+            .filter(|it: &&ProcessStat| port_pids.as_ref().is_none_or(|pids| pids.contains(&it.pid)))
+            .filter(|it: &&ProcessStat| {
+                path_reasons
+                    .as_ref()
+                    .is_none_or(|reasons| reasons.contains_key(&it.pid))
+            })
             .cloned()
+            .map(|mut it| {
+                if let Some(reasons) = path_reasons.as_ref().and_then(|reasons| reasons.get(&it.pid)) {
+                    it.holds_path = reasons.clone();
+                }
+                it
+            })
             .collect();
 
         if self.group_by_exe {
@@ -112,6 +220,72 @@ impl App {
         self.move_cursor(0);
     }
 
+    /// PIDs of processes listening on any of the ports, None if no port is filtered
+    fn find_port_pids(&mut self, ports: &[u16]) -> Option<HashSet<String>> {
+        if ports.is_empty() {
+            return None;
+        }
+        let mut pids = HashSet::new();
+        let mut unresolved_sockets = 0;
+        for port in ports {
+            if !self.port_owners_cache.contains_key(port) {
+                match find_port_owners(*port) {
+                    Ok(owners) => self.port_owners_cache.insert(*port, owners),
+                    Err(err) => {
+                        self.filter_note = Some(format!("Failed to look up port {}: {}", port, err));
+                        continue;
+                    }
+                };
+            }
+            let owners = &self.port_owners_cache[port];
+            pids.extend(owners.pids.iter().cloned());
+            unresolved_sockets += owners.unresolved_sockets;
+        }
+        if unresolved_sockets > 0 {
+            self.filter_note = Some(format!(
+                "{} socket(s) owned by processes of other users, run psycho as root to see them",
+                unresolved_sockets
+            ));
+        }
+        Some(pids)
+    }
+
+    /// How processes keep any of the paths busy, by PID, None if no path is filtered
+    fn find_path_reasons(&mut self, paths: &[PathBuf]) -> Option<HashMap<String, Vec<String>>> {
+        if paths.is_empty() {
+            return None;
+        }
+        let mut reasons: HashMap<String, Vec<String>> = HashMap::new();
+        let mut uninspected = 0;
+        for path in paths {
+            // scanning all processes is expensive, so partial paths typed in the filter are scanned once
+            if !self.path_holders_cache.contains_key(path) {
+                match find_path_holders(path) {
+                    Ok(holders) => self.path_holders_cache.insert(path.clone(), holders),
+                    Err(err) => {
+                        self.filter_note = Some(format!("{:#}", err));
+                        continue;
+                    }
+                };
+            }
+            let holders = &self.path_holders_cache[path];
+            for (pid, pid_reasons) in &holders.reasons {
+                reasons
+                    .entry(pid.clone())
+                    .or_default()
+                    .extend(pid_reasons.iter().cloned());
+            }
+            uninspected = uninspected.max(holders.uninspected);
+        }
+        if uninspected > 0 && self.filter_note.is_none() {
+            self.filter_note = Some(format!(
+                "{} process(es) of other users not inspected, run psycho as root to see them",
+                uninspected
+            ));
+        }
+        Some(reasons)
+    }
+
     pub fn get_sort_fn(&self) -> fn(&ProcessStat, &ProcessStat) -> std::cmp::Ordering {
         match self.ordering {
             Ordering::ByUptime => |x, y| {
@@ -135,35 +309,1547 @@ impl App {
                 }
                 return x.pid_num.cmp(&y.pid_num).reverse();
             },
+            Ordering::ByOomScore => |x, y| {
+                let oom_score_cmp = x.oom_score.cmp(&y.oom_score);
+                if oom_score_cmp != Equal {
+                    return oom_score_cmp.reverse();
+                }
+                x.pid_num.cmp(&y.pid_num).reverse()
+            },
+            Ordering::ByDiskIo => |x, y| {
+                let disk_io_cmp = x.disk_io_rate().partial_cmp(&y.disk_io_rate()).unwrap_or(Equal);
+                if disk_io_cmp != Equal {
+                    return disk_io_cmp.reverse();
+                }
+                x.pid_num.cmp(&y.pid_num).reverse()
+            },
         }
     }
 
     pub fn confirm_process(&mut self) {
-        if self.process_cursor >= self.filtered_processes.len() {
+        if self.process_cursor >= self.filtered_processes.len() && self.marked_pids.is_empty() {
             return;
         }
         self.window_focus = WindowFocus::SignalPick;
-        self.menu_action_cursor = 0;
+        // killing a service's process only makes systemd restart it, so suggest stopping the unit instead
+        self.menu_action_cursor = match self.target_service() {
+            Some(_) => self
+                .known_menu_actions
+                .iter()
+                .position(|it| matches!(it.operation, Operation::SystemdUnit(UnitCommand::Stop)))
+                .unwrap_or(0),
+            None => 0,
+        };
+    }
+
+    /// Systemd service managing the first targeted process
+    fn target_service(&self) -> Option<SystemdUnit> {
+        let pid = self.target_pids().into_iter().next()?;
+        let process = self.proc_stats.processes.iter().find(|it| it.pid == pid)?;
+        process.systemd_unit.clone().filter(|it| it.is_service())
+    }
+
+    fn target_unit_names(&self) -> String {
+        self.target_pids()
+            .iter()
+            .filter_map(|pid| self.proc_stats.processes.iter().find(|it| it.pid == *pid))
+            .filter_map(|it| it.systemd_unit.as_ref().map(|unit| unit.name.clone()))
+            .unique()
+            .join(" ")
+    }
+
+    pub fn menu_title(&self) -> String {
+        match self.target_service() {
+            Some(unit) => format!("Choose a command (managed by {}, which may restart it)", unit.name),
+            None => "Choose a command".to_string(),
+        }
     }
 
     pub fn confirm_signal(&mut self) {
-        let process: &ProcessStat = &self.filtered_processes[self.process_cursor];
         let action: &MenuAction = &self.known_menu_actions[self.menu_action_cursor];
-        match action.operation {
-            Operation::KillSignal { template } => {
-                let res = kill_pid(&process.pid, template);
-                if res.is_err() {
-                    self.error_message = Some(res.err().unwrap().to_string());
+        // operations with a pick are planned once the value is picked
+        if self.dry_run && !matches!(action.operation, Operation::ShowDetails) && !action.operation.needs_pick() {
+            self.show_info(self.plan_operation(&action.name, &action.operation));
+            return;
+        }
+        let destructive = match &action.operation {
+            Operation::KillSignal { signal, superuser } => Some(is_destructive(*signal, *superuser)),
+            Operation::TerminateThenKill { superuser } => Some(is_destructive(libc::SIGKILL, *superuser)),
+            Operation::Restart => Some(true),
+            Operation::TogglePause => Some(is_destructive(libc::SIGSTOP, false)),
+            Operation::Custom(custom) => Some(custom.superuser),
+            Operation::SystemdUnit(_) => Some(true),
+            _ => None,
+        };
+        if matches!(action.operation, Operation::FreezeCgroup | Operation::KillCgroup) {
+            if action.confirm.requires_confirmation(true) {
+                let title = format!("Confirm: {}", action.name);
+                self.open_cgroup_confirmation(title);
+                return;
+            }
+        } else if let Some(destructive) = destructive {
+            if action.confirm.requires_confirmation(destructive) {
+                let title = match &action.operation {
+                    Operation::SystemdUnit(_) => format!("Confirm: {} {}", action.name, self.target_unit_names()),
+                    _ => format!("Confirm: {}", action.name),
+                };
+                self.open_kill_confirmation(title, WindowFocus::SignalPick);
+                return;
+            }
+        }
+        self.execute_menu_action();
+    }
+
+    fn execute_menu_action(&mut self) {
+        let action: &MenuAction = &self.known_menu_actions[self.menu_action_cursor];
+        match action.operation.clone() {
+            Operation::KillSignal { signal, superuser } => {
+                let pids = self.target_pids();
+                if !self.guard_protected(&pids, signal) {
+                    return;
                 }
+                match self.signal_processes(&pids, signal, superuser) {
+                    Some(KillError::PermissionDenied) if !superuser => {
+                        if let Some(cursor) = self.find_superuser_action(signal) {
+                            self.menu_action_cursor = cursor;
+                            return;
+                        }
+                    }
+                    Some(KillError::InvalidSignal) | Some(KillError::AuthenticationRequired) => return,
+                    _ => {}
+                }
+                self.marked_pids.clear();
                 self.refresh_processes();
             }
+            Operation::TerminateThenKill { superuser } => {
+                let pids = self.target_pids();
+                if !self.guard_protected(&pids, libc::SIGKILL) || (superuser && !self.authenticate_for(&pids)) {
+                    return;
+                }
+                self.start_kill_sequence(pids, superuser);
+                self.marked_pids.clear();
+            }
+            Operation::Restart => {
+                let pids = self.target_pids();
+                let [pid] = pids.as_slice() else {
+                    self.error_message =
+                        Some("Select a single process to restart, not a group or marked processes.".to_string());
+                    return;
+                };
+                let snapshot = match ProcessSnapshot::capture(pid) {
+                    Ok(snapshot) => snapshot,
+                    Err(err) => {
+                        self.error_message = Some(format!("Can't restart process {}: {:#}", pid, err));
+                        return;
+                    }
+                };
+                if !self.guard_protected(&pids, libc::SIGKILL) {
+                    return;
+                }
+                self.pending_restarts = vec![(pid.clone(), snapshot)];
+                self.start_kill_sequence(pids, false);
+                self.marked_pids.clear();
+            }
+            Operation::KillTree { signal, superuser } => {
+                self.tree_kill_nodes = collect_subtree(&self.proc_stats.processes, &self.target_pids());
+                if self.tree_kill_nodes.is_empty() {
+                    self.error_message = Some("Selected process no longer exists".to_string());
+                } else {
+                    self.tree_kill_signal = signal;
+                    self.tree_kill_superuser = superuser;
+                    self.tree_kill_scroll = 0;
+                    self.window_focus = WindowFocus::TreeKillPreview;
+                    return;
+                }
+            }
+            Operation::TogglePause => {
+                self.toggle_pause();
+            }
+            Operation::Renice => {
+                let current = self.describe_target(|it| it.nice.to_string());
+                self.open_option_pick(format!("Choose nice value (current: {})", current), nice_pick_options());
+                return;
+            }
+            Operation::ChangeScheduler => {
+                let current = self.describe_target(|it| it.format_priority());
+                let title = format!("Choose scheduling policy (current: {})", current);
+                self.open_option_pick(title, scheduler_pick_options());
+                return;
+            }
+            Operation::ChangeIoPriority => {
+                let current = self.describe_target(|it| it.io_priority.describe(it.nice));
+                let title = format!("Choose I/O priority (current: {})", current);
+                self.open_option_pick(title, io_priority_pick_options());
+                return;
+            }
+            Operation::ChangeOomScoreAdj => {
+                let current = self.describe_target(|it| it.oom_score_adj.to_string());
+                let title = format!("Choose OOM score adjustment (current: {})", current);
+                self.open_option_pick(title, oom_score_adj_pick_options());
+                return;
+            }
+            Operation::EditAffinity => {
+                self.open_affinity_pick();
+                return;
+            }
+            Operation::PickSignal { superuser } => {
+                self.signal_catalogue_superuser = superuser;
+                self.window_focus = WindowFocus::SignalCatalogue;
+                return;
+            }
             Operation::ShowDetails => {
-                self.show_info(process.details(&self.sys_stat));
+                self.show_info(self.target_details());
+            }
+            Operation::FreezeCgroup => {
+                if !self.run_cgroup_action(false) {
+                    return;
+                }
+                self.refresh_processes();
+            }
+            Operation::KillCgroup => {
+                if !self.run_cgroup_action(true) {
+                    return;
+                }
+                self.refresh_processes();
+            }
+            Operation::SystemdUnit(command) => {
+                if !self.run_unit_action(command) {
+                    return;
+                }
+                self.refresh_processes();
+            }
+            Operation::Custom(custom) => {
+                if !self.run_custom_action(&custom) {
+                    return;
+                }
+                self.refresh_processes();
             }
         }
         self.window_focus = WindowFocus::Browse;
     }
 
+    pub fn confirm_signal_catalogue(&mut self) {
+        let signal = self.signal_catalogue[self.signal_catalogue_cursor].number;
+        let superuser = self.signal_catalogue_superuser;
+        let policy = self.known_menu_actions[self.menu_action_cursor].confirm;
+        if policy.requires_confirmation(is_destructive(signal, superuser)) {
+            let mut title = format!("Confirm: send {}", signal_name(signal));
+            if superuser {
+                title = format!("{} as Superuser", title);
+            }
+            self.open_kill_confirmation(title, WindowFocus::SignalCatalogue);
+            return;
+        }
+        self.execute_catalogue_signal();
+    }
+
+    fn execute_catalogue_signal(&mut self) {
+        let pids = self.target_pids();
+        let signal = self.signal_catalogue[self.signal_catalogue_cursor].number;
+        let superuser = self.signal_catalogue_superuser;
+        if self.dry_run {
+            let name = format!("Send {}", signal_name(signal));
+            self.show_info(self.plan_operation(&name, &Operation::KillSignal { signal, superuser }));
+            return;
+        }
+        if !self.guard_protected(&pids, signal) {
+            return;
+        }
+        match self.signal_processes(&pids, signal, superuser) {
+            Some(KillError::PermissionDenied) if !superuser => {
+                self.signal_catalogue_superuser = true;
+                return;
+            }
+            Some(KillError::InvalidSignal) | Some(KillError::AuthenticationRequired) => return,
+            _ => {}
+        }
+        self.marked_pids.clear();
+        self.refresh_processes();
+        self.window_focus = WindowFocus::Browse;
+    }
+
+    fn open_kill_confirmation(&mut self, title: String, origin: WindowFocus) {
+        self.open_processes_confirmation(title, vec![], &self.target_pids(), origin);
+    }
+
+    /// Ask for confirmation with a summary of all processes in the cgroups of the targeted processes
+    fn open_cgroup_confirmation(&mut self, title: String) {
+        let (cgroups, errors) = self.target_cgroups();
+        if let Some(error) = errors.first().filter(|_| cgroups.is_empty()) {
+            self.error_message = Some(format!("{:#}", error));
+            return;
+        }
+        let mut intro: Vec<String> = vec![];
+        let mut pids: Vec<String> = vec![];
+        for cgroup in &cgroups {
+            let members = cgroup.member_pids();
+            let state = if cgroup.is_frozen() { ", frozen" } else { "" };
+            intro.push(format!(
+                "Cgroup {} contains {} processes{}",
+                cgroup.name,
+                members.len(),
+                state
+            ));
+            pids.extend(members);
+        }
+        intro.extend(errors.iter().map(|error| format!("{:#}", error)));
+        intro.push(String::new());
+        self.open_processes_confirmation(title, intro, &pids, WindowFocus::SignalPick);
+    }
+
+    fn open_processes_confirmation(&mut self, title: String, intro: Vec<String>, pids: &[String], origin: WindowFocus) {
+        let processes: Vec<&ProcessStat> = pids
+            .iter()
+            .filter_map(|pid| self.proc_stats.processes.iter().find(|it| it.pid == *pid))
+            .collect();
+        let total_memory: f64 = processes.iter().map(|it| it.memory_usage).sum();
+        let total_cpu: f64 = processes.iter().map(|it| it.cpu_usage).sum();
+        let mut lines = intro;
+        lines.extend([
+            format!(
+                "Affected processes: {}, total resident memory (RSS): {}, total CPU usage: {}",
+                processes.len(),
+                total_memory.to_percent1(),
+                total_cpu.to_percent1()
+            ),
+            String::new(),
+            format!("{:<8} {:<12} {:>6} {:>6}  {}", "PID", "User", "RSS", "CPU", "Command"),
+        ]);
+        for process in processes {
+            lines.push(format!(
+                "{:<8} {:<12} {:>6} {:>6}  {}",
+                process.pid,
+                process.user_name,
+                process.memory_usage.to_percent1(),
+                process.format_cpu_usage(),
+                process.display_name
+            ));
+        }
+        self.kill_confirm_title = title;
+        self.kill_confirm_message = lines.join("\n");
+        self.kill_confirm_scroll = 0;
+        self.kill_confirm_origin = origin;
+        self.window_focus = WindowFocus::KillConfirm;
+    }
+
+    pub fn confirm_kill(&mut self) {
+        self.window_focus = self.kill_confirm_origin;
+        self.retry_action(self.kill_confirm_origin);
+    }
+
+    pub fn confirm_tree_kill(&mut self) {
+        let pids: Vec<String> = match self.tree_kill_order {
+            TreeKillOrder::ChildrenFirst => children_first_pids(&self.tree_kill_nodes),
+            TreeKillOrder::AllAtOnce => self.tree_kill_nodes.iter().map(|it| it.process.pid.clone()).collect(),
+        };
+        let superuser = self.tree_kill_superuser;
+        if self.dry_run {
+            let order = match self.tree_kill_order {
+                TreeKillOrder::ChildrenFirst => "children first",
+                TreeKillOrder::AllAtOnce => "all at once",
+            };
+            let name = format!("Send {} to process tree, {}", signal_name(self.tree_kill_signal), order);
+            let mut steps = self.plan_signal(&pids, self.tree_kill_signal, superuser);
+            if self.tree_kill_order == TreeKillOrder::ChildrenFirst && ends_process(self.tree_kill_signal) {
+                steps.push(format!(
+                    "Wait up to {}s for each level of the tree to exit before signalling its parents",
+                    self.grace_period.as_secs_f64()
+                ));
+            }
+            self.show_info(self.plan_report(&name, &pids, steps));
+            return;
+        }
+        if !self.guard_protected(&pids, self.tree_kill_signal) {
+            return;
+        }
+        if self.tree_kill_order == TreeKillOrder::ChildrenFirst {
+            if superuser && !self.authenticate_for(&pids) {
+                return;
+            }
+            self.start_tree_kill();
+            self.marked_pids.clear();
+            self.window_focus = WindowFocus::Browse;
+            return;
+        }
+        match self.signal_processes(&pids, self.tree_kill_signal, superuser) {
+            Some(KillError::PermissionDenied) if !superuser => {
+                self.tree_kill_superuser = true;
+                return;
+            }
+            Some(KillError::InvalidSignal) | Some(KillError::AuthenticationRequired) => return,
+            _ => {}
+        }
+        self.marked_pids.clear();
+        self.refresh_processes();
+        self.window_focus = WindowFocus::Browse;
+    }
+
+    pub fn toggle_tree_kill_order(&mut self) {
+        self.tree_kill_order = match self.tree_kill_order {
+            TreeKillOrder::ChildrenFirst => TreeKillOrder::AllAtOnce,
+            TreeKillOrder::AllAtOnce => TreeKillOrder::ChildrenFirst,
+        };
+    }
+
+    fn open_option_pick(&mut self, title: String, items: Vec<PickOption>) {
+        self.option_pick_title = title;
+        self.option_pick_items = items;
+        self.option_pick_cursor = 0;
+        self.window_focus = WindowFocus::OptionPick;
+    }
+
+    pub fn confirm_option_pick(&mut self) {
+        let option = self.option_pick_items[self.option_pick_cursor].clone();
+        let pids = self.target_pids();
+        if self.dry_run {
+            let name = format!("Set {}", option.value.describe());
+            let steps = self.plan_pick_value(&option.value, &pids);
+            self.show_info(self.plan_report(&name, &pids, steps));
+            return;
+        }
+        let results: Vec<(String, Result<()>)> = match option.value {
+            PickValue::Nice(nice) => pids
+                .into_iter()
+                .map(|pid| {
+                    let res = set_nice(&pid, nice);
+                    (pid, res)
+                })
+                .collect(),
+            PickValue::IoPriority(priority) => pids
+                .into_iter()
+                .map(|pid| {
+                    let res = set_io_priority(&pid, priority);
+                    (pid, res)
+                })
+                .collect(),
+            PickValue::OomScoreAdj(score_adj) => pids
+                .into_iter()
+                .map(|pid| {
+                    let res = set_oom_score_adj(&pid, score_adj);
+                    (pid, res)
+                })
+                .collect(),
+            PickValue::Scheduler { policy, priority } => pids
+                .into_iter()
+                .map(|pid| {
+                    let res = set_scheduler(&pid, policy, priority);
+                    (pid, res)
+                })
+                .collect(),
+        };
+        if self.authentication_requested(&results) {
+            return;
+        }
+        self.report_results(format!("Setting {}", option.value.describe()), results);
+        self.refresh_processes();
+        self.window_focus = WindowFocus::Browse;
+    }
+
+    fn open_affinity_pick(&mut self) {
+        let cpu_num = self.sys_stat.cpu_num;
+        let first_pid = self.target_pids().first().cloned().unwrap_or_default();
+        self.affinity_cores = get_affinity(&first_pid, cpu_num).unwrap_or(vec![true; cpu_num]);
+        self.affinity_all_threads = true;
+        self.affinity_group_members = true;
+        self.affinity_cursor = 0;
+        self.window_focus = WindowFocus::AffinityPick;
+    }
+
+    /// Toggle a core, or one of the options listed after the cores
+    pub fn toggle_affinity_item(&mut self) {
+        let cpu_num = self.affinity_cores.len();
+        match self.affinity_cursor {
+            cursor if cursor < cpu_num => self.affinity_cores[cursor] = !self.affinity_cores[cursor],
+            cursor if cursor == cpu_num => self.affinity_all_threads = !self.affinity_all_threads,
+            _ => self.affinity_group_members = !self.affinity_group_members,
+        }
+    }
+
+    pub fn toggle_all_affinity_cores(&mut self) {
+        let all_selected = self.affinity_cores.iter().all(|it| *it);
+        self.affinity_cores.iter_mut().for_each(|it| *it = !all_selected);
+    }
+
+    pub fn confirm_affinity_pick(&mut self) {
+        let cores: Vec<usize> = (0..self.affinity_cores.len())
+            .filter(|core| self.affinity_cores[*core])
+            .collect();
+        if cores.is_empty() {
+            self.error_message = Some("Select at least one CPU core".to_string());
+            return;
+        }
+        let pids: Vec<String> = match self.filtered_processes.get(self.process_cursor) {
+            Some(process) if !self.affinity_group_members && self.marked_pids.is_empty() => vec![process.pid.clone()],
+            _ => self.target_pids(),
+        };
+        let all_threads = self.affinity_all_threads;
+        if self.dry_run {
+            let cores_list = cores.iter().map(|core| core.to_string()).join(",");
+            let name = format!("Set CPU affinity to cores {}", cores_list);
+            let steps = self.plan_affinity(&cores, all_threads, &pids);
+            self.show_info(self.plan_report(&name, &pids, steps));
+            return;
+        }
+        let results: Vec<(String, Result<()>)> = pids
+            .into_iter()
+            .map(|pid| {
+                let res = set_affinity(&pid, &cores, all_threads);
+                (pid, res)
+            })
+            .collect();
+        if self.authentication_requested(&results) {
+            return;
+        }
+        let cores_list = cores.iter().map(|core| core.to_string()).join(",");
+        self.report_results(format!("Setting CPU affinity to cores {}", cores_list), results);
+        self.window_focus = WindowFocus::Browse;
+    }
+
+    /// Ask for authentication, then retry the action of the current window
+    fn request_authentication(&mut self) {
+        self.escalation_origin = self.window_focus;
+        match escalation::backend().reads_password_from_stdin() {
+            true => {
+                self.password_input.clear();
+                self.password_error = None;
+                self.window_focus = WindowFocus::PasswordPrompt;
+            }
+            false => self.terminal_escalation_pending = true,
+        }
+    }
+
+    /// Request authentication if any privileged command failed for the lack of it
+    fn authentication_requested(&mut self, results: &[(String, Result<()>)]) -> bool {
+        let required = results
+            .iter()
+            .any(|(_, res)| matches!(res, Err(err) if is_authentication_required(err)));
+        if required {
+            self.request_authentication();
+        }
+        required
+    }
+
+    /// Make sure privileged commands can run before starting a background operation on other users' processes
+    fn authenticate_for(&mut self, pids: &[String]) -> bool {
+        let euid = unsafe { libc::geteuid() };
+        let foreign = pids.iter().any(|pid| {
+            let process = self.proc_stats.processes.iter().find(|it| it.pid == *pid);
+            process.is_some_and(|it| it.user_id != Some(euid))
+        });
+        // the backend will prompt on the terminal for each privileged step anyway
+        if euid == 0 || !foreign || matches!(escalation::auth_mode(), AuthMode::Terminal) {
+            return true;
+        }
+        match execute_privileged("true".to_string()) {
+            Ok(()) => true,
+            Err(err) if is_authentication_required(&err) => {
+                self.request_authentication();
+                false
+            }
+            Err(err) => {
+                self.error_message = Some(format!("Failed to authenticate: {:#}", err));
+                false
+            }
+        }
+    }
+
+    pub fn confirm_password(&mut self) {
+        let password = std::mem::take(&mut self.password_input);
+        if let Err(err) = validate_password(&password) {
+            self.password_error = Some(err.to_string());
+            return;
+        }
+        escalation::use_password(password);
+        self.window_focus = self.escalation_origin;
+        self.retry_action(self.escalation_origin);
+        escalation::reset_auth_mode();
+    }
+
+    pub fn cancel_password_prompt(&mut self) {
+        self.password_input.clear();
+        self.window_focus = self.escalation_origin;
+    }
+
+    /// Retry the action while the terminal is handed over to the escalation backend, so that it can prompt on it
+    pub fn escalate_in_terminal(&mut self) {
+        self.terminal_escalation_pending = false;
+        log(format!(
+            "Authenticating with {} in the terminal to run the action as Superuser",
+            escalation::backend().program()
+        )
+        .as_str());
+        escalation::use_terminal();
+        self.window_focus = self.escalation_origin;
+        self.retry_action(self.escalation_origin);
+        escalation::reset_auth_mode();
+    }
+
+    /// Run again the action started from the given window, once the user confirmed or authenticated it
+    fn retry_action(&mut self, origin: WindowFocus) {
+        match origin {
+            WindowFocus::Browse => self.toggle_pause(),
+            WindowFocus::SignalCatalogue => self.execute_catalogue_signal(),
+            WindowFocus::TreeKillPreview => self.confirm_tree_kill(),
+            WindowFocus::OptionPick => self.confirm_option_pick(),
+            WindowFocus::AffinityPick => self.confirm_affinity_pick(),
+            _ => self.execute_menu_action(),
+        }
+    }
+
+    /// Describe what the operation would do to the targeted processes, without doing it
+    fn plan_operation(&self, name: &str, operation: &Operation) -> String {
+        let pids = self.target_pids();
+        let steps = self.plan_steps(operation, &pids);
+        self.plan_report(name, &pids, steps)
+    }
+
+    fn plan_report(&self, name: &str, pids: &[String], steps: Vec<String>) -> String {
+        let mut lines = vec![
+            format!("Dry run of {:?}, nothing was executed.", name),
+            String::new(),
+            format!("Targeted processes: {}", pids.len()),
+        ];
+        for pid in pids {
+            let Some(process) = self.proc_stats.processes.iter().find(|it| it.pid == *pid) else {
+                lines.push(format!("PID {}: no longer exists", pid));
+                continue;
+            };
+            let protection = match &process.protection {
+                Some(it) if it.is_refused() => format!(", refused: {}", it.describe()),
+                Some(it) => format!(", asks to type its name: {}", it.describe()),
+                None => String::new(),
+            };
+            lines.push(format!(
+                "PID {} (user {}, start time {}{}): {}",
+                pid, process.user_name, process.start_time, protection, process.display_name
+            ));
+        }
+        lines.push(String::new());
+        lines.push("Steps:".to_string());
+        lines.extend(steps);
+        lines.join("\n")
+    }
+
+    fn plan_steps(&self, operation: &Operation, pids: &[String]) -> Vec<String> {
+        let fallback = |cmd: String| format!("  if not permitted: {}", describe_privileged(&cmd));
+        match operation {
+            Operation::KillSignal { signal, superuser } => self.plan_signal(pids, *signal, *superuser),
+            Operation::TerminateThenKill { superuser } => self.plan_kill_sequence(pids, *superuser),
+            Operation::Restart => {
+                let [pid] = pids else {
+                    return vec!["Refused: restart needs a single process".to_string()];
+                };
+                let snapshot = match ProcessSnapshot::capture(pid) {
+                    Ok(snapshot) => snapshot,
+                    Err(err) => return vec![format!("Refused: can't restart process {}: {:#}", pid, err)],
+                };
+                let mut steps = vec![format!(
+                    "Capture executable {}, command line {:?}, working directory {} and {} environment variables",
+                    snapshot.exe.display(),
+                    snapshot.command_line(),
+                    snapshot.cwd.display(),
+                    snapshot.env.len()
+                )];
+                steps.extend(self.plan_kill_sequence(pids, false));
+                steps.push(format!(
+                    "Relaunch the captured command detached, in a new session, as user {}, if it exited",
+                    snapshot.owner.uid
+                ));
+                steps
+            }
+            Operation::TogglePause => {
+                let all_stopped = pids.iter().all(|pid| {
+                    self.proc_stats
+                        .processes
+                        .iter()
+                        .any(|it| it.pid == *pid && it.is_stopped())
+                });
+                let signal = if all_stopped { libc::SIGCONT } else { libc::SIGSTOP };
+                self.plan_signal(pids, signal, false)
+            }
+            // planned with the picked value by `plan_pick_value`, `plan_affinity` or `plan_signal`
+            Operation::PickSignal { .. }
+            | Operation::KillTree { .. }
+            | Operation::Renice
+            | Operation::ChangeScheduler
+            | Operation::ChangeIoPriority
+            | Operation::ChangeOomScoreAdj
+            | Operation::EditAffinity => vec!["Pick a value first".to_string()],
+            Operation::ShowDetails => vec!["Show process details".to_string()],
+            Operation::SystemdUnit(command) => {
+                let mut units: Vec<SystemdUnit> = vec![];
+                let mut steps = vec![];
+                for pid in pids {
+                    let process = self.proc_stats.processes.iter().find(|it| it.pid == *pid);
+                    match process.and_then(|it| it.systemd_unit.clone()) {
+                        Some(unit) if units.contains(&unit) => {}
+                        Some(unit) => units.push(unit),
+                        None => steps.push(format!("Skip PID {}: it doesn't belong to a systemd unit", pid)),
+                    }
+                }
+                for unit in units {
+                    steps.push(match unit_command_line(&unit, *command) {
+                        (cmd, true) => describe_privileged(&cmd),
+                        (cmd, false) => cmd,
+                    });
+                }
+                steps
+            }
+            Operation::FreezeCgroup | Operation::KillCgroup => {
+                let (cgroups, errors) = self.target_cgroups();
+                let thaw = cgroups.iter().all(|it| it.is_frozen());
+                let (file, value) = match operation {
+                    Operation::KillCgroup => ("cgroup.kill", "1"),
+                    _ if thaw => ("cgroup.freeze", "0"),
+                    _ => ("cgroup.freeze", "1"),
+                };
+                let mut steps: Vec<String> = errors.iter().map(|err| format!("Skip: {:#}", err)).collect();
+                for cgroup in cgroups {
+                    let path = cgroup.path.join(file).to_string_lossy().to_string();
+                    steps.push(format!(
+                        "Write {} to {} ({} processes)",
+                        value,
+                        path,
+                        cgroup.member_pids().len()
+                    ));
+                    steps.push(fallback(format!("echo {} > {}", value, shell_quote(&path))));
+                }
+                steps
+            }
+            Operation::Custom(custom) => pids
+                .iter()
+                .map(|pid| {
+                    let process = self.proc_stats.processes.iter().find(|it| it.pid == *pid);
+                    let command = process
+                        .ok_or(anyhow!("process no longer exists"))
+                        .and_then(|it| render_template(&custom.command, it));
+                    match (command, custom.superuser) {
+                        (Ok(cmd), true) => format!("PID {}: {}", pid, describe_privileged(&cmd)),
+                        (Ok(cmd), false) => format!("PID {}: sh -c {}", pid, shell_quote(&cmd)),
+                        (Err(err), _) => format!("PID {}: {:#}", pid, err),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Steps of sending a signal to each process, in the given order
+    fn plan_signal(&self, pids: &[String], signal: i32, superuser: bool) -> Vec<String> {
+        let name = signal_name(signal);
+        let mut steps = vec![];
+        for pid in pids {
+            let start_time = self
+                .start_time_of(pid)
+                .map(|it| it.to_string())
+                .unwrap_or("-".to_string());
+            steps.push(format!(
+                "pidfd_send_signal(pidfd_open({}), {}), or kill({}, {}) after checking start time {} without pidfd support",
+                pid, name, pid, name, start_time
+            ));
+            if superuser {
+                let cmd = format!("kill -{} {}", signal, pid);
+                steps.push(format!("  if not permitted: {}", describe_privileged(&cmd)));
+            }
+        }
+        steps
+    }
+
+    fn plan_kill_sequence(&self, pids: &[String], superuser: bool) -> Vec<String> {
+        let mut steps = self.plan_signal(pids, libc::SIGTERM, superuser);
+        steps.push(format!(
+            "Wait up to {}s for them to exit",
+            self.grace_period.as_secs_f64()
+        ));
+        steps.push(format!(
+            "Send SIGKILL the same way to the ones still alive and wait up to {}s",
+            KILL_WAIT.as_secs_f64()
+        ));
+        steps
+    }
+
+    /// Steps of setting the picked value on every thread of each process
+    fn plan_pick_value(&self, value: &PickValue, pids: &[String]) -> Vec<String> {
+        let mut steps = vec![];
+        for pid in pids {
+            let tids = match process_tasks(pid) {
+                Ok(tids) => tids,
+                Err(err) => {
+                    steps.push(format!("PID {}: {:#}", pid, err));
+                    continue;
+                }
+            };
+            let threads = tids.join(", ");
+            let (step, cmd) = match value {
+                PickValue::Nice(nice) => (
+                    format!("setpriority(PRIO_PROCESS, TID, {}) for threads {}", nice, threads),
+                    renice_command(*nice, &tids),
+                ),
+                PickValue::Scheduler { policy, priority } => (
+                    format!(
+                        "sched_setscheduler(TID, {}, priority {}) for threads {}",
+                        policy.name(),
+                        priority,
+                        threads
+                    ),
+                    chrt_command(*policy, *priority, pid),
+                ),
+                PickValue::IoPriority(priority) => (
+                    format!(
+                        "ioprio_set(IOPRIO_WHO_PROCESS, TID, {} {}) for threads {}",
+                        priority.class.name(),
+                        priority.level,
+                        threads
+                    ),
+                    ionice_command(*priority, &tids),
+                ),
+                PickValue::OomScoreAdj(score_adj) => (
+                    format!("Write {} to /proc/{}/oom_score_adj", score_adj, pid),
+                    oom_score_adj_command(pid, *score_adj),
+                ),
+            };
+            steps.push(format!("PID {}: {}", pid, step));
+            steps.push(format!("  if not permitted: {}", describe_privileged(&cmd)));
+        }
+        steps
+    }
+
+    fn plan_affinity(&self, cores: &[usize], all_threads: bool, pids: &[String]) -> Vec<String> {
+        let cores_list = cores.iter().map(|core| core.to_string()).join(",");
+        let mut steps = vec![];
+        for pid in pids {
+            let tids = match all_threads {
+                true => process_tasks(pid),
+                false => Ok(vec![pid.clone()]),
+            };
+            match tids {
+                Ok(tids) => steps.push(format!(
+                    "PID {}: sched_setaffinity(TID, cores {}) for threads {}",
+                    pid,
+                    cores_list,
+                    tids.join(", ")
+                )),
+                Err(err) => {
+                    steps.push(format!("PID {}: {:#}", pid, err));
+                    continue;
+                }
+            }
+            let cmd = taskset_command(cores, all_threads, pid);
+            steps.push(format!("  if not permitted: {}", describe_privileged(&cmd)));
+        }
+        steps
+    }
+
+    /// Refuse signals that could take down the system, ask to type the process name for other protected processes.
+    /// Returns true if the signal can be sent.
+    fn guard_protected(&mut self, pids: &[String], signal: i32) -> bool {
+        if is_harmless_signal(signal) {
+            return true;
+        }
+        self.guard_protected_action(pids, &format!("send {} to", signal_name(signal)))
+    }
+
+    /// Refuse or ask to confirm a disruptive action on protected processes, e.g. "send SIGKILL to"
+    fn guard_protected_action(&mut self, pids: &[String], action: &str) -> bool {
+        let protected: Vec<(String, String, Protection)> = pids
+            .iter()
+            .filter_map(|pid| self.proc_stats.processes.iter().find(|it| it.pid == *pid))
+            .filter_map(|it| Some((it.pid.clone(), it.name.clone(), it.protection.clone()?)))
+            .collect();
+        if let Some((pid, name, protection)) = protected.iter().find(|(_, _, it)| it.is_refused()) {
+            self.error_message = Some(format!(
+                "Refusing to {} process {} ({}), it is {}.",
+                action,
+                pid,
+                name,
+                protection.describe()
+            ));
+            return false;
+        }
+        let unconfirmed: Vec<&(String, String, Protection)> = protected
+            .iter()
+            .filter(|(pid, _, _)| !self.protection_confirmed.contains(pid))
+            .collect();
+        let Some((_, first_name, _)) = unconfirmed.first() else {
+            return true;
+        };
+        let mut lines = vec![format!(
+            "About to {} protected processes, which may take down the system or your session:",
+            action
+        )];
+        for (pid, name, protection) in &unconfirmed {
+            lines.push(format!("PID {} ({}): {}", pid, name, protection.describe()));
+        }
+        lines.push(String::new());
+        lines.push(format!("Type {:?} to proceed anyway.", first_name));
+        self.protected_confirm_name = first_name.clone();
+        self.protected_confirm_message = lines.join("\n");
+        self.protected_confirm_input.clear();
+        self.protected_confirm_pids = unconfirmed.iter().map(|(pid, _, _)| pid.clone()).collect();
+        self.protected_confirm_origin = self.window_focus;
+        self.window_focus = WindowFocus::ProtectedConfirm;
+        false
+    }
+
+    pub fn confirm_protected(&mut self) {
+        if self.protected_confirm_input != self.protected_confirm_name {
+            self.protected_confirm_input.clear();
+            return;
+        }
+        self.protection_confirmed.append(&mut self.protected_confirm_pids);
+        self.window_focus = self.protected_confirm_origin;
+        self.retry_action(self.protected_confirm_origin);
+    }
+
+    /// Show errors of an operation applied to each process: as an error for one process, as a summary for many
+    fn report_results(&mut self, description: String, results: Vec<(String, Result<()>)>) {
+        self.audit_results(&description, &results);
+        if let [(pid, Err(err))] = results.as_slice() {
+            self.error_message = Some(format!("{} of process {} failed: {:#}", description, pid, err));
+            return;
+        }
+        if results.len() <= 1 {
+            return;
+        }
+        let succeeded = results.iter().filter(|(_, res)| res.is_ok()).count();
+        let mut lines = vec![format!(
+            "{} succeeded for {} of {} processes:",
+            description,
+            succeeded,
+            results.len()
+        )];
+        for (pid, res) in &results {
+            match res {
+                Ok(()) => lines.push(format!("PID {pid}: OK")),
+                Err(err) => lines.push(format!("PID {pid}: {err:#}")),
+            }
+        }
+        self.show_info(lines.join("\n"));
+    }
+
+    /// Run a user-defined command for every targeted process, showing its output if requested
+    fn run_custom_action(&mut self, custom: &CustomAction) -> bool {
+        let pids = self.target_pids();
+        // a custom command may kill or stop the process just like a signal
+        if !self.guard_protected_action(&pids, &format!("run {:?} on", custom.command)) {
+            return false;
+        }
+        let multiple = pids.len() > 1;
+        let mut outputs: Vec<String> = vec![];
+        let mut results: Vec<(String, Result<()>)> = vec![];
+        for pid in pids {
+            let output = match self.proc_stats.processes.iter().find(|it| it.pid == pid) {
+                Some(process) => custom.execute(process),
+                None => Err(anyhow!("process no longer exists")),
+            };
+            match output {
+                Ok(output) => {
+                    let output = output.trim_end();
+                    let output = if output.is_empty() { "(no output)" } else { output };
+                    match multiple {
+                        true => outputs.push(format!("PID {}:\n{}", pid, output)),
+                        false => outputs.push(output.to_string()),
+                    }
+                    results.push((pid, Ok(())));
+                }
+                Err(err) => {
+                    outputs.push(format!("PID {}: {:#}", pid, err));
+                    results.push((pid, Err(err)));
+                }
+            }
+        }
+        if self.authentication_requested(&results) {
+            return false;
+        }
+        if !custom.show_output || matches!(results.as_slice(), [(_, Err(_))]) {
+            self.report_results(format!("Running {:?}", custom.command), results);
+            return true;
+        }
+        self.audit_results(&format!("Running {:?}", custom.command), &results);
+        self.show_info(outputs.join("\n\n"));
+        true
+    }
+
+    /// Append an entry about a process to the audit log, with its attributes from before the action
+    /// Record the action in the audit log, telling the operator when it can't be written,
+    /// e.g. because a shared log file isn't writable by this user
+    fn audit(&mut self, action: &str, signal: &str, pid: &str, outcome: &str) {
+        let process = self.proc_stats.processes.iter().find(|it| it.pid == pid);
+        if let Err(err) = self.audit_log.record(action, signal, pid, process, outcome) {
+            self.error_message = Some(format!(
+                "The action was executed, but it couldn't be recorded in the audit log: {:#}",
+                err
+            ));
+        }
+    }
+
+    fn audit_results(&mut self, action: &str, results: &[(String, Result<()>)]) {
+        for (pid, result) in results {
+            let outcome = match result {
+                Ok(()) => "OK".to_string(),
+                Err(err) => format!("failed: {:#}", err),
+            };
+            self.audit(action, "", pid, &outcome);
+        }
+    }
+
+    pub fn show_audit_history(&mut self) {
+        let path = match &self.audit_log.path {
+            Some(path) => path.display().to_string(),
+            None => {
+                self.error_message = Some("Audit log is disabled, its location is unknown".to_string());
+                return;
+            }
+        };
+        match self.audit_log.history() {
+            Ok(entries) if entries.is_empty() => self.show_info(format!("No actions recorded yet in {}", path)),
+            Ok(entries) => self.show_info(format!(
+                "Action history from {}, newest first:\n\n{}",
+                path,
+                entries.join("\n")
+            )),
+            Err(err) => self.error_message = Some(format!("{:#}", err)),
+        }
+    }
+
+    /// Distinct cgroups of the targeted processes, and errors for processes outside of a cgroup v2 hierarchy
+    fn target_cgroups(&self) -> (Vec<Cgroup>, Vec<anyhow::Error>) {
+        let mut cgroups: Vec<Cgroup> = vec![];
+        let mut errors = vec![];
+        for pid in self.target_pids() {
+            match Cgroup::of_process(&pid) {
+                Ok(cgroup) if cgroups.contains(&cgroup) => {}
+                Ok(cgroup) => cgroups.push(cgroup),
+                Err(err) => errors.push(err),
+            }
+        }
+        (cgroups, errors)
+    }
+
+    /// Freeze (or thaw if already frozen) or kill the cgroups of the targeted processes
+    fn run_cgroup_action(&mut self, kill: bool) -> bool {
+        let (cgroups, errors) = self.target_cgroups();
+        if let Some(error) = errors.first().filter(|_| cgroups.is_empty()) {
+            self.error_message = Some(format!("{:#}", error));
+            return true;
+        }
+        let cgroup_members: Vec<Vec<String>> = cgroups.iter().map(|it| it.member_pids()).collect();
+        let members: Vec<String> = cgroup_members.concat();
+        let thaw = !kill && cgroups.iter().all(|it| it.is_frozen());
+        let signal = match (kill, thaw) {
+            (true, _) => libc::SIGKILL,
+            (false, true) => libc::SIGCONT,
+            (false, false) => libc::SIGSTOP,
+        };
+        if !self.guard_protected(&members, signal) {
+            return false;
+        }
+        let results: Vec<(String, Result<()>)> = cgroups
+            .iter()
+            .map(|cgroup| {
+                let result = match kill {
+                    true => cgroup.kill(),
+                    false => cgroup.set_frozen(!thaw),
+                };
+                (cgroup.name.clone(), result)
+            })
+            .collect();
+        if self.authentication_requested(&results) {
+            return false;
+        }
+        let description = match (kill, thaw) {
+            (true, _) => "Killing",
+            (false, true) => "Thawing",
+            (false, false) => "Freezing",
+        };
+        let mut lines: Vec<String> = results
+            .iter()
+            .zip(cgroup_members)
+            .map(|((name, result), members)| match result {
+                Ok(()) => format!("{} cgroup {}: OK, {} processes", description, name, members.len()),
+                Err(err) => format!(
+                    "{} cgroup {} with {} processes failed: {:#}",
+                    description,
+                    name,
+                    members.len(),
+                    err
+                ),
+            })
+            .collect();
+        for ((name, result), line) in results.iter().zip(&lines) {
+            let outcome = match result {
+                Ok(()) => line.clone(),
+                Err(err) => format!("failed: {:#}", err),
+            };
+            self.audit(&format!("{} cgroup {}", description, name), "", "-", &outcome);
+        }
+        lines.extend(errors.iter().map(|error| format!("{:#}", error)));
+        self.show_info(lines.join("\n"));
+        true
+    }
+
+    /// Stop, restart or kill the systemd units of the targeted processes, each unit once
+    fn run_unit_action(&mut self, command: UnitCommand) -> bool {
+        let mut units: Vec<(String, SystemdUnit)> = vec![]; // first targeted PID of every unit
+        let mut results: Vec<(String, Result<()>)> = vec![];
+        for pid in self.target_pids() {
+            let process = self.proc_stats.processes.iter().find(|it| it.pid == pid);
+            match process.and_then(|it| it.systemd_unit.clone()) {
+                Some(unit) if units.iter().any(|(_, it)| *it == unit) => {}
+                Some(unit) => units.push((pid, unit)),
+                None => results.push((pid, Err(anyhow!("it doesn't belong to a systemd unit")))),
+            }
+        }
+        // the command affects every process of the unit, not only the targeted ones
+        let unit_pids: Vec<String> = self
+            .proc_stats
+            .processes
+            .iter()
+            .filter(|it| {
+                it.systemd_unit
+                    .as_ref()
+                    .is_some_and(|unit| units.iter().any(|(_, it)| it == unit))
+            })
+            .map(|it| it.pid.clone())
+            .collect();
+        if !self.guard_protected(&unit_pids, libc::SIGTERM) {
+            return false;
+        }
+        for (pid, unit) in units {
+            results.push((pid, run_unit_command(&unit, command)));
+        }
+        if self.authentication_requested(&results) {
+            return false;
+        }
+        self.report_results(format!("systemctl {}", command.verb()), results);
+        true
+    }
+
+    /// Attribute of the targeted processes, or "mixed" if they differ
+    fn describe_target(&self, attribute: impl Fn(&ProcessStat) -> String) -> String {
+        let values: Vec<String> = self
+            .target_pids()
+            .iter()
+            .filter_map(|pid| self.proc_stats.processes.iter().find(|it| it.pid == *pid))
+            .map(attribute)
+            .unique()
+            .collect();
+        match values.as_slice() {
+            [value] => value.clone(),
+            _ => "mixed".to_string(),
+        }
+    }
+
+    pub fn open_column_pick(&mut self) {
+        self.column_pick_cursor = 0;
+        self.window_focus = WindowFocus::ColumnPick;
+    }
+
+    pub fn toggle_extra_column(&mut self) {
+        let column = ExtraColumn::all()[self.column_pick_cursor];
+        match self.extra_columns.contains(&column) {
+            true => {
+                self.extra_columns.retain(|it| *it != column);
+                if column.ordering().as_ref() == Some(&self.ordering) {
+                    self.set_process_ordering(Ordering::default());
+                }
+            }
+            false => {
+                let had_breakdown = self.needs_memory_breakdown();
+                self.extra_columns.push(column);
+                self.extra_columns
+                    .sort_by_key(|it| ExtraColumn::all().iter().position(|c| c == it));
+                if !had_breakdown && self.needs_memory_breakdown() {
+                    self.refresh_processes();
+                }
+            }
+        }
+    }
+
+    /// PIDs an action applies to: all marked processes, otherwise the selected row
+    pub fn target_pids(&self) -> Vec<String> {
+        if !self.marked_pids.is_empty() {
+            let mut pids: Vec<String> = self.marked_pids.iter().cloned().collect();
+            pids.sort_by_key(|pid| pid.parse::<u32>().unwrap_or(0));
+            return pids;
+        }
+        match self.filtered_processes.get(self.process_cursor) {
+            Some(process) => process.member_pids(),
+            None => vec![],
+        }
+    }
+
+    fn target_details(&self) -> String {
+        if self.marked_pids.is_empty() {
+            return self.filtered_processes[self.process_cursor].details(&self.sys_stat);
+        }
+        self.target_pids()
+            .iter()
+            .filter_map(|pid| self.proc_stats.processes.iter().find(|it| it.pid == *pid))
+            .map(|process| process.details(&self.sys_stat))
+            .collect::<Vec<String>>()
+            .join("\n---\n\n")
+    }
+
+    /// Pause or resume from the process list, asking first if the menu's Pause / Resume action is configured to
+    pub fn request_toggle_pause(&mut self) {
+        let action = self
+            .known_menu_actions
+            .iter()
+            .find(|it| matches!(it.operation, Operation::TogglePause));
+        let destructive = is_destructive(libc::SIGSTOP, false);
+        match action {
+            Some(action) if !self.dry_run && action.confirm.requires_confirmation(destructive) => {
+                if self.target_pids().is_empty() {
+                    return;
+                }
+                let title = format!("Confirm: {}", action.name);
+                self.open_kill_confirmation(title, WindowFocus::Browse);
+            }
+            _ => self.toggle_pause(),
+        }
+    }
+
+    /// Stop the targeted processes, or resume them if all of them are already stopped
+    pub fn toggle_pause(&mut self) {
+        let pids = self.target_pids();
+        if pids.is_empty() {
+            return;
+        }
+        if self.dry_run {
+            self.show_info(self.plan_operation("Pause / Resume", &Operation::TogglePause));
+            return;
+        }
+        let all_stopped = pids.iter().all(|pid| {
+            self.proc_stats
+                .processes
+                .iter()
+                .any(|it| it.pid == *pid && it.is_stopped())
+        });
+        let signal = match all_stopped {
+            true => libc::SIGCONT,
+            false => libc::SIGSTOP,
+        };
+        if !self.guard_protected(&pids, signal) {
+            return;
+        }
+        self.signal_processes(&pids, signal, false);
+        self.refresh_processes();
+    }
+
+    pub fn toggle_mark(&mut self) {
+        let pids = match self.filtered_processes.get(self.process_cursor) {
+            Some(process) => process.member_pids(),
+            None => return,
+        };
+        if pids.iter().all(|pid| self.marked_pids.contains(pid)) {
+            for pid in pids {
+                self.marked_pids.remove(&pid);
+            }
+        } else {
+            self.marked_pids.extend(pids);
+        }
+        self.move_cursor(1);
+    }
+
+    pub fn is_marked(&self, process: &ProcessStat) -> bool {
+        process.member_pids().iter().all(|pid| self.marked_pids.contains(pid))
+    }
+
+    /// Forget marks of processes that no longer exist, or whose PID was reused by another process
+    fn prune_marks(&mut self) {
+        let processes = &self.proc_stats.processes;
+        let previous_processes = &self.previous_proc_stats.processes;
+        self.marked_pids.retain(|pid| {
+            let start_time = |list: &Vec<ProcessStat>| list.iter().find(|it| it.pid == *pid).map(|it| it.start_time);
+            start_time(processes).is_some() && start_time(processes) == start_time(previous_processes)
+        });
+    }
+
+    /// Start time of a process as seen on the list, to make sure a signal reaches that very process
+    fn start_time_of(&self, pid: &str) -> Option<u64> {
+        self.proc_stats
+            .processes
+            .iter()
+            .find(|it| it.pid == pid)
+            .map(|it| it.start_time)
+            .filter(|start_time| *start_time > 0)
+    }
+
+    /// Send a signal to each process and report the outcome.
+    /// Returns the error that calls for a follow-up: retrying as Superuser or picking another signal.
+    fn signal_processes(&mut self, pids: &[String], signal: i32, superuser: bool) -> Option<KillError> {
+        let action = match superuser {
+            true => "Send signal as Superuser",
+            false => "Send signal",
+        };
+        if let [pid] = pids {
+            let result = kill_pid(pid, self.start_time_of(pid), signal, superuser);
+            self.audit_kill_result(action, signal, pid, &result);
+            let err = result.err()?;
+            match err {
+                KillError::AuthenticationRequired => self.request_authentication(),
+                _ => self.report_kill_error(&err, pid, signal),
+            }
+            return Some(err);
+        }
+
+        let results: Vec<(&String, Result<(), KillError>)> = pids
+            .iter()
+            .map(|pid| (pid, kill_pid(pid, self.start_time_of(pid), signal, superuser)))
+            .collect();
+        for (pid, result) in &results {
+            self.audit_kill_result(action, signal, pid, result);
+        }
+        if results
+            .iter()
+            .any(|(_, res)| matches!(res, Err(KillError::AuthenticationRequired)))
+        {
+            self.request_authentication();
+            return Some(KillError::AuthenticationRequired);
+        }
+        let succeeded = results.iter().filter(|(_, res)| res.is_ok()).count();
+        let mut lines = vec![format!(
+            "Sent {} to {} of {} processes:",
+            signal_name(signal),
+            succeeded,
+            results.len()
+        )];
+        for (pid, res) in &results {
+            match res {
+                Ok(()) => lines.push(format!("PID {pid}: OK")),
+                Err(err) => lines.push(format!("PID {pid}: {err}")),
+            }
+        }
+        self.show_info(lines.join("\n"));
+
+        let mut errors = results.into_iter().filter_map(|(_, res)| res.err());
+        errors.find(|err| matches!(err, KillError::PermissionDenied | KillError::InvalidSignal))
+    }
+
+    fn audit_kill_result(&mut self, action: &str, signal: i32, pid: &str, result: &Result<(), KillError>) {
+        let outcome = match result {
+            Ok(()) => "OK".to_string(),
+            Err(err) => format!("failed: {}", err),
+        };
+        self.audit(action, &signal_name(signal), pid, &outcome);
+    }
+
+    fn start_kill_sequence(&mut self, pids: Vec<String>, superuser: bool) {
+        let Some(sender) = self.progress_sender() else {
+            return;
+        };
+        self.kill_sequence_log = vec![];
+        self.show_info("Terminating…".to_string());
+        let targets = pids
+            .into_iter()
+            .map(|pid| {
+                let start_time = self.start_time_of(&pid);
+                (pid, start_time)
+            })
+            .collect();
+        spawn_kill_sequence(targets, self.grace_period, superuser, escalation::auth_mode(), sender);
+    }
+
+    /// Signal the previewed tree level by level in the background, children first
+    fn start_tree_kill(&mut self) {
+        let Some(sender) = self.progress_sender() else {
+            return;
+        };
+        self.kill_sequence_log = vec![];
+        self.show_info(format!("Sending {}…", signal_name(self.tree_kill_signal)));
+        let levels = children_first_levels(&self.tree_kill_nodes)
+            .iter()
+            .map(|level| {
+                level
+                    .iter()
+                    .map(|node| {
+                        let start_time = Some(node.process.start_time).filter(|it| *it > 0);
+                        (node.process.pid.clone(), start_time)
+                    })
+                    .collect()
+            })
+            .collect();
+        spawn_tree_kill(
+            levels,
+            self.tree_kill_signal,
+            self.grace_period,
+            self.tree_kill_superuser,
+            escalation::auth_mode(),
+            sender,
+        );
+    }
+
+    fn progress_sender(&mut self) -> Option<Sender<Event>> {
+        if self.event_sender.is_none() {
+            self.error_message = Some("Event loop is not running".to_string());
+        }
+        self.event_sender.clone()
+    }
+
+    pub fn on_kill_progress(&mut self, progress: KillProgress) {
+        match progress {
+            KillProgress::Step(message) => {
+                self.kill_sequence_log.push(message);
+            }
+            KillProgress::Finished { outcomes } => {
+                for (pid, outcome) in &outcomes {
+                    let summary = match outcome {
+                        KillOutcome::Terminated => format!("Process {pid} terminated gracefully."),
+                        KillOutcome::Killed => format!("Process {pid} was killed forcefully."),
+                        KillOutcome::AlreadyGone => format!("Process {pid} is already gone."),
+                        KillOutcome::PermissionDenied => format!("Permission denied to signal process {pid}."),
+                        KillOutcome::StillAlive => format!(
+                            "Process {pid} is still alive after SIGKILL, it may be stuck in uninterruptible sleep."
+                        ),
+                        KillOutcome::Signalled => format!("Sent SIGTERM to process {pid}."),
+                        KillOutcome::Failed(error) => format!("Failed to kill process {pid}: {error}"),
+                    };
+                    let signal = match outcome {
+                        KillOutcome::Killed | KillOutcome::StillAlive => "SIGTERM, SIGKILL",
+                        _ => "SIGTERM",
+                    };
+                    self.audit("Terminate, then kill", signal, pid, &summary);
+                    self.kill_sequence_log.push(summary);
+                }
+                self.relaunch_pending(&outcomes);
+                self.refresh_processes();
+                let denied_pid = outcomes
+                    .iter()
+                    .find(|(_, outcome)| *outcome == KillOutcome::PermissionDenied)
+                    .map(|(pid, _)| pid);
+                if let Some(pid) = denied_pid {
+                    if self.offer_superuser_kill_sequence(pid) {
+                        self.kill_sequence_log
+                            .push("Close this window to retry the sequence as Superuser.".to_string());
+                    }
+                }
+            }
+            KillProgress::TreeFinished {
+                signal,
+                superuser,
+                outcomes,
+            } => {
+                let name = signal_name(signal);
+                let action = match superuser {
+                    true => "Send signal as Superuser",
+                    false => "Send signal",
+                };
+                for (pid, outcome) in &outcomes {
+                    let summary = match outcome {
+                        KillOutcome::Terminated | KillOutcome::Killed => format!("Process {pid} exited after {name}."),
+                        KillOutcome::Signalled | KillOutcome::StillAlive if ends_process(signal) => {
+                            format!("Process {pid} is still running after {name}.")
+                        }
+                        KillOutcome::Signalled | KillOutcome::StillAlive => format!("Sent {name} to process {pid}."),
+                        KillOutcome::AlreadyGone => format!("Process {pid} is already gone."),
+                        KillOutcome::PermissionDenied => format!("Permission denied to signal process {pid}."),
+                        KillOutcome::Failed(error) => format!("Failed to signal process {pid}: {error}"),
+                    };
+                    self.audit(action, &name, pid, &summary);
+                    self.kill_sequence_log.push(summary);
+                }
+                self.refresh_processes();
+                let denied = outcomes.iter().any(|(_, it)| *it == KillOutcome::PermissionDenied);
+                if denied && !superuser && self.offer_superuser_tree_kill() {
+                    self.kill_sequence_log
+                        .push("Close this window to retry the remaining processes as Superuser.".to_string());
+                }
+            }
+        }
+        self.show_info(self.kill_sequence_log.join("\n"));
+    }
+
+    /// Start again the processes that were restarted, once they have exited
+    fn relaunch_pending(&mut self, outcomes: &[(String, KillOutcome)]) {
+        for (pid, snapshot) in std::mem::take(&mut self.pending_restarts) {
+            let exited = outcomes
+                .iter()
+                .any(|(it, outcome)| *it == pid && matches!(outcome, KillOutcome::Terminated | KillOutcome::Killed));
+            if !exited {
+                self.audit("Restart", "", &pid, "not restarted, it didn't exit");
+                self.kill_sequence_log.push(format!("Process {pid} was not restarted."));
+                continue;
+            }
+            let message = match snapshot.relaunch() {
+                Ok(new_pid) => format!("Restarted {:?} as process {}.", snapshot.command_line(), new_pid),
+                Err(err) => format!("Failed to restart process {}: {:#}", pid, err),
+            };
+            self.audit("Restart", "", &pid, &message);
+            self.kill_sequence_log.push(message);
+        }
+    }
+
+    /// Reopen the action menu on the process with the Superuser kill sequence selected
+    /// Preview again the processes of the tree that are still running, to be signalled as Superuser
+    fn offer_superuser_tree_kill(&mut self) -> bool {
+        let processes = &self.proc_stats.processes;
+        self.tree_kill_nodes.retain(|node| {
+            processes
+                .iter()
+                .any(|it| it.pid == node.process.pid && it.start_time == node.process.start_time)
+        });
+        if self.tree_kill_nodes.is_empty() {
+            return false;
+        }
+        self.tree_kill_superuser = true;
+        self.tree_kill_scroll = 0;
+        self.window_focus = WindowFocus::TreeKillPreview;
+        true
+    }
+
+    fn offer_superuser_kill_sequence(&mut self, pid: &str) -> bool {
+        let process_cursor = self
+            .filtered_processes
+            .iter()
+            .position(|it| it.member_pids().iter().any(|member| member == pid));
+        let action_cursor = self
+            .known_menu_actions
+            .iter()
+            .position(|it| matches!(it.operation, Operation::TerminateThenKill { superuser: true }));
+        match (process_cursor, action_cursor) {
+            (Some(process_cursor), Some(action_cursor)) => {
+                self.process_cursor = process_cursor;
+                self.proc_list_table_state.select(Some(process_cursor));
+                self.menu_action_cursor = action_cursor;
+                self.window_focus = WindowFocus::SignalPick;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn report_kill_error(&mut self, err: &KillError, pid: &str, signal: i32) {
+        let signal_name = signal_name(signal);
+        match err {
+            KillError::NoSuchProcess => {
+                self.show_info(format!("Process {pid} is already gone."));
+            }
+            KillError::ProcessReplaced => {
+                self.show_info(format!(
+                    "Process {pid} is already gone and its PID now belongs to another process, {signal_name} was not sent."
+                ));
+            }
+            KillError::PermissionDenied => {
+                self.error_message = Some(format!(
+                    "Permission denied to send {signal_name} to process {pid}. \
+It belongs to another user, retry as Superuser to send it with elevated privileges."
+                ));
+            }
+            KillError::InvalidSignal => {
+                self.error_message = Some(format!("Invalid signal: {signal_name} is not supported by the system."));
+            }
+            _ => {
+                self.error_message = Some(format!("Failed to send {signal_name} to process {pid}: {err}"));
+            }
+        }
+    }
+
+    fn find_superuser_action(&self, signal: i32) -> Option<usize> {
+        self.known_menu_actions
+            .iter()
+            .position(|it| matches!(it.operation, Operation::KillSignal { signal: s, superuser: true } if s == signal))
+    }
+
     pub fn format_sys_stats(&self) -> Vec<Line> {
         self.sys_stat
             .summarize(&self.init_stat, &self.previous_stat)
@@ -173,6 +1859,22 @@ impl App {
             .collect()
     }
 
+    /// Focus the filter to search for processes by the port they listen on, e.g. `:8080`
+    pub fn start_port_filter(&mut self) {
+        self.start_special_filter(':');
+    }
+
+    /// Focus the filter to search for processes keeping a path busy, e.g. `@/mnt/usb`
+    pub fn start_path_filter(&mut self) {
+        self.start_special_filter('@');
+    }
+
+    fn start_special_filter(&mut self, prefix: char) {
+        self.filter_text = prefix.to_string();
+        self.window_focus = WindowFocus::ProcessFilter;
+        self.filter_processes();
+    }
+
     pub fn filter_clear(&mut self) {
         self.filter_text.clear();
         self.filter_processes();
@@ -214,13 +1916,20 @@ impl App {
     }
 
     pub fn toggle_group_by_exe(&mut self) {
+        let had_breakdown = self.needs_memory_breakdown();
         self.group_by_exe = !self.group_by_exe;
-        self.filter_processes();
+        match !had_breakdown && self.needs_memory_breakdown() {
+            true => self.refresh_processes(),
+            false => self.filter_processes(),
+        }
     }
 
     pub fn enrich_proc_stats(&mut self) {
         for proc_stat in &mut self.proc_stats.processes {
             proc_stat.cpu_usage = proc_stat.calculate_cpu_usage(&self.previous_proc_stats.processes);
+            (proc_stat.disk_read_rate, proc_stat.disk_write_rate) =
+                proc_stat.calculate_disk_io_rates(&self.previous_proc_stats.processes);
+            proc_stat.protection = self.protection_rules.check(proc_stat);
         }
     }
 }
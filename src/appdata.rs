@@ -3,6 +3,14 @@ pub enum WindowFocus {
     Browse,
     ProcessFilter,
     SignalPick,
+    SignalCatalogue,
+    TreeKillPreview,
+    KillConfirm,
+    OptionPick,
+    ColumnPick,
+    AffinityPick,
+    PasswordPrompt,
+    ProtectedConfirm,
     SystemStats,
 }
 
@@ -17,6 +25,8 @@ pub enum Ordering {
     ByUptime,
     ByMemory,
     ByCpu,
+    ByOomScore,
+    ByDiskIo,
 }
 
 impl Default for Ordering {
@@ -24,3 +34,86 @@ impl Default for Ordering {
         Ordering::ByMemory
     }
 }
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum TreeKillOrder {
+    #[default]
+    ChildrenFirst,
+    AllAtOnce,
+}
+
+/// Optional columns of the process list, shown in this order
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExtraColumn {
+    Priority,
+    OomScore,
+    Pss,
+    Uss,
+    RssAnon,
+    RssFile,
+    Shmem,
+    Swap,
+    DiskIo,
+    Unit,
+}
+
+impl ExtraColumn {
+    pub fn all() -> Vec<ExtraColumn> {
+        vec![
+            ExtraColumn::Priority,
+            ExtraColumn::OomScore,
+            ExtraColumn::Pss,
+            ExtraColumn::Uss,
+            ExtraColumn::RssAnon,
+            ExtraColumn::RssFile,
+            ExtraColumn::Shmem,
+            ExtraColumn::Swap,
+            ExtraColumn::DiskIo,
+            ExtraColumn::Unit,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ExtraColumn::Priority => "Scheduling class and priority",
+            ExtraColumn::OomScore => "OOM killer score",
+            ExtraColumn::Pss => "Proportional memory (PSS), shared pages split among their users",
+            ExtraColumn::Uss => "Unique memory (USS), freed by killing the process",
+            ExtraColumn::RssAnon => "Resident anonymous memory",
+            ExtraColumn::RssFile => "Resident file-backed memory",
+            ExtraColumn::Shmem => "Resident shared memory (shmem)",
+            ExtraColumn::Swap => "Swapped out memory",
+            ExtraColumn::DiskIo => "Disk I/O rate, read and written per second",
+            ExtraColumn::Unit => "Systemd unit",
+        }
+    }
+
+    /// Whether the column shows the memory breakdown, which is costly to read for every process
+    pub fn needs_memory_breakdown(&self) -> bool {
+        matches!(
+            self,
+            ExtraColumn::Pss
+                | ExtraColumn::Uss
+                | ExtraColumn::RssAnon
+                | ExtraColumn::RssFile
+                | ExtraColumn::Shmem
+                | ExtraColumn::Swap
+        )
+    }
+
+    /// Ordering that sorts by this column, if it is sortable
+    pub fn ordering(&self) -> Option<Ordering> {
+        match self {
+            ExtraColumn::Priority
+            | ExtraColumn::Pss
+            | ExtraColumn::Uss
+            | ExtraColumn::RssAnon
+            | ExtraColumn::RssFile
+            | ExtraColumn::Shmem
+            | ExtraColumn::Swap
+            | ExtraColumn::Unit => None,
+            ExtraColumn::OomScore => Some(Ordering::ByOomScore),
+            ExtraColumn::DiskIo => Some(Ordering::ByDiskIo),
+        }
+    }
+}
@@ -0,0 +1,343 @@
+use std::ffi::{CStr, CString};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{Context, Result};
+
+use crate::logs::log;
+use crate::sysinfo::ProcessStat;
+
+/// Number of most recent entries shown in the history view
+const HISTORY_LIMIT: usize = 500;
+
+/// One executed action on one process, stored as a line of tab-separated `key=value` fields
+#[derive(Debug, Default, Clone)]
+pub struct AuditEntry {
+    pub timestamp: String, // UTC, e.g. 2024-05-01T12:30:00Z
+    pub user: String,
+    pub action: String,
+    pub signal: String, // empty for actions that don't send signals
+    pub pid: String,
+    pub exe: String,
+    pub cmdline: String,
+    pub start_time: String,
+    pub outcome: String,
+}
+
+impl AuditEntry {
+    fn fields(&self) -> [(&'static str, &String); 9] {
+        [
+            ("time", &self.timestamp),
+            ("user", &self.user),
+            ("action", &self.action),
+            ("signal", &self.signal),
+            ("pid", &self.pid),
+            ("start_time", &self.start_time),
+            ("exe", &self.exe),
+            ("cmdline", &self.cmdline),
+            ("outcome", &self.outcome),
+        ]
+    }
+
+    fn to_line(&self) -> String {
+        self.fields()
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, escape(value)))
+            .collect::<Vec<String>>()
+            .join("\t")
+    }
+
+    fn from_line(line: &str) -> AuditEntry {
+        let mut entry = AuditEntry::default();
+        for field in line.split('\t') {
+            let Some((key, value)) = field.split_once('=') else {
+                continue;
+            };
+            let value = unescape(value);
+            match key {
+                "time" => entry.timestamp = value,
+                "user" => entry.user = value,
+                "action" => entry.action = value,
+                "signal" => entry.signal = value,
+                "pid" => entry.pid = value,
+                "start_time" => entry.start_time = value,
+                "exe" => entry.exe = value,
+                "cmdline" => entry.cmdline = value,
+                "outcome" => entry.outcome = value,
+                _ => {}
+            }
+        }
+        entry
+    }
+
+    fn describe(&self) -> String {
+        let signal = match self.signal.is_empty() {
+            true => String::new(),
+            false => format!(" {}", self.signal),
+        };
+        let cmdline = match self.cmdline.is_empty() {
+            true => String::new(),
+            false => format!(" ({})", self.cmdline.replace(['\n', '\t'], " ")),
+        };
+        format!(
+            "{} {}: {}{} PID {}{}: {}",
+            self.timestamp.replace('T', " ").trim_end_matches('Z'),
+            self.user,
+            self.action,
+            signal,
+            self.pid,
+            cmdline,
+            self.outcome
+        )
+    }
+}
+
+/// Append-only record of actions executed on processes, kept across sessions
+#[derive(Debug, Default)]
+pub struct AuditLog {
+    pub path: Option<PathBuf>,
+    pub user: String, // invoking user, e.g. "alice (1000)" or "alice (1000) as root (0)" under sudo
+}
+
+impl AuditLog {
+    pub fn new(path: Option<PathBuf>) -> Self {
+        AuditLog {
+            path: path.or_else(default_audit_path),
+            user: invoking_user(),
+        }
+    }
+
+    /// Record the outcome of an action on a process, as it was seen before the action
+    pub fn record(
+        &self,
+        action: &str,
+        signal: &str,
+        pid: &str,
+        process: Option<&ProcessStat>,
+        outcome: &str,
+    ) -> Result<()> {
+        let entry = AuditEntry {
+            timestamp: format_utc_timestamp(SystemTime::now()),
+            user: self.user.clone(),
+            action: action.to_string(),
+            signal: signal.to_string(),
+            pid: pid.to_string(),
+            exe: process.map(|it| it.exe.clone()).unwrap_or_default(),
+            cmdline: process.map(|it| it.display_name.clone()).unwrap_or_default(),
+            start_time: process.map(|it| it.start_time.to_string()).unwrap_or_default(),
+            outcome: outcome.to_string(),
+        };
+        self.append(&entry).inspect_err(|err| {
+            log(format!("Failed to write audit log: {:#}", err).as_str());
+        })
+    }
+
+    fn append(&self, entry: &AuditEntry) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).context(format!("failed to create {}", dir.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .mode(0o600)
+            .open(path)
+            .context(format!("failed to open {}", path.display()))?;
+        writeln!(file, "{}", entry.to_line()).context(format!("failed to write {}", path.display()))
+    }
+
+    /// Most recent entries, newest first
+    pub fn history(&self) -> Result<Vec<String>> {
+        let Some(path) = &self.path else {
+            return Ok(vec![]);
+        };
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(vec![]),
+            Err(err) => return Err(err).context(format!("failed to read {}", path.display())),
+        };
+        Ok(content
+            .lines()
+            .rev()
+            .take(HISTORY_LIMIT)
+            .map(|line| AuditEntry::from_line(line).describe())
+            .collect())
+    }
+}
+
+/// Default location: `$XDG_STATE_HOME/psycho-killer/audit.log` or `~/.local/state/psycho-killer/audit.log`
+pub fn default_audit_path() -> Option<PathBuf> {
+    let state_dir = match std::env::var_os("XDG_STATE_HOME").filter(|it| !it.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".local").join("state"),
+    };
+    Some(state_dir.join("psycho-killer").join("audit.log"))
+}
+
+/// User running psycho, and the person behind it when it was escalated with sudo or doas
+fn invoking_user() -> String {
+    let uid = unsafe { libc::getuid() };
+    let user = describe_uid(uid);
+    match operator_uid().filter(|it| *it != uid) {
+        Some(operator) => format!("{} as {}", describe_uid(operator), user),
+        None => user,
+    }
+}
+
+/// Login UID of the session, kept by the kernel across sudo and su, otherwise the user who ran sudo or doas
+fn operator_uid() -> Option<libc::uid_t> {
+    let login_uid = std::fs::read_to_string("/proc/self/loginuid")
+        .ok()
+        .and_then(|it| it.trim().parse::<libc::uid_t>().ok())
+        .filter(|it| *it != libc::uid_t::MAX); // unset, e.g. in containers
+    login_uid
+        .or_else(|| std::env::var("SUDO_UID").ok()?.parse::<libc::uid_t>().ok())
+        .or_else(|| user_id(&std::env::var("DOAS_USER").ok()?))
+}
+
+fn describe_uid(uid: libc::uid_t) -> String {
+    format!("{} ({})", user_name(uid).unwrap_or_default(), uid)
+}
+
+/// Look up the user with NSS, so that users from LDAP and the like are resolved too
+fn user_name(uid: libc::uid_t) -> Option<String> {
+    lookup_passwd(
+        |passwd, buffer, result| unsafe { libc::getpwuid_r(uid, passwd, buffer.as_mut_ptr(), buffer.len(), result) },
+        |passwd| unsafe { CStr::from_ptr(passwd.pw_name) }.to_string_lossy().to_string(),
+    )
+}
+
+fn user_id(name: &str) -> Option<libc::uid_t> {
+    let name = CString::new(name).ok()?;
+    lookup_passwd(
+        |passwd, buffer, result| unsafe {
+            libc::getpwnam_r(name.as_ptr(), passwd, buffer.as_mut_ptr(), buffer.len(), result)
+        },
+        |passwd| passwd.pw_uid,
+    )
+}
+
+/// Call a reentrant passwd lookup, growing the buffer for its strings as needed,
+/// and read the entry while the buffer it points into is still alive
+fn lookup_passwd<T>(
+    lookup: impl Fn(&mut libc::passwd, &mut Vec<libc::c_char>, &mut *mut libc::passwd) -> libc::c_int,
+    read: impl Fn(&libc::passwd) -> T,
+) -> Option<T> {
+    let mut buffer: Vec<libc::c_char> = vec![0; 1024];
+    loop {
+        let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        match lookup(&mut passwd, &mut buffer, &mut result) {
+            libc::ERANGE if buffer.len() < 1 << 20 => buffer.resize(buffer.len() * 2, 0),
+            0 if !result.is_null() => return Some(read(&passwd)),
+            _ => return None,
+        }
+    }
+}
+
+fn format_utc_timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map(|it| it.as_secs()).unwrap_or(0);
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        (seconds / 3600) % 24,
+        (seconds / 60) % 60,
+        seconds % 60
+    )
+}
+
+/// Gregorian date from days since the Unix epoch (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let day_of_era = z.rem_euclid(146097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+    let year = year_of_era + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\t', "\\t").replace('\n', "\\n")
+}
+
+fn unescape(value: &str) -> String {
+    let mut result = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('t') => result.push('\t'),
+                Some('n') => result.push('\n'),
+                Some(other) => result.push(other),
+                None => result.push('\\'),
+            },
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn test_escape_round_trip() {
+        for value in [
+            "plain",
+            "tab\there",
+            "new\nline",
+            "back\\slash",
+            "literal \\t and \\n",
+            "trailing\\",
+            "",
+        ] {
+            assert_eq!(unescape(&escape(value)), value);
+            assert!(!escape(value).contains(['\t', '\n']));
+        }
+    }
+
+    #[test]
+    fn test_entry_line_round_trip() {
+        let entry = AuditEntry {
+            timestamp: "2024-01-01T12:00:00Z".to_string(),
+            user: "alice (1000) as root (0)".to_string(),
+            action: "Kill".to_string(),
+            signal: "SIGKILL".to_string(),
+            pid: "42".to_string(),
+            exe: "/usr/bin/sleep".to_string(),
+            cmdline: "sleep\t1000 'a=b'".to_string(),
+            start_time: "12345".to_string(),
+            outcome: "failed: no such process\nretried".to_string(),
+        };
+        let parsed = AuditEntry::from_line(&entry.to_line());
+        assert_eq!(parsed.fields(), entry.fields());
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11016), (2000, 2, 29));
+        assert_eq!(civil_from_days(19723), (2024, 1, 1));
+    }
+
+    #[test]
+    fn test_format_utc_timestamp() {
+        let time = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(format_utc_timestamp(time), "2023-11-14T22:13:20Z");
+    }
+}
@@ -0,0 +1,163 @@
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::escalation::execute_privileged;
+use crate::logs::log;
+use crate::strings::shell_quote;
+
+/// Control group of the unified (v2) hierarchy
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cgroup {
+    pub name: String,  // path within the hierarchy, e.g. /user.slice/user-1000.slice/session-2.scope
+    pub path: PathBuf, // directory in the mounted cgroup2 filesystem
+}
+
+impl Cgroup {
+    /// Cgroup v2 the process belongs to, from the `0::/path` entry of `/proc/PID/cgroup`
+    pub fn of_process(pid: &str) -> Result<Cgroup> {
+        let content = std::fs::read_to_string(format!("/proc/{}/cgroup", pid))
+            .context(format!("process {} no longer exists", pid))?;
+        let name = content
+            .lines()
+            .find_map(|line| line.strip_prefix("0::"))
+            .ok_or(anyhow!("process {} is not in a cgroup v2 hierarchy", pid))?
+            .to_string();
+        if name == "/" {
+            return Err(anyhow!(
+                "process {} is in the root cgroup, which can't be frozen or killed",
+                pid
+            ));
+        }
+        let (mount_root, mount_point) = find_cgroup2_mount()?;
+        Ok(Cgroup {
+            path: hierarchy_path(&mount_point, &mount_root, &name)?,
+            name,
+        })
+    }
+
+    /// PIDs of all processes in the cgroup and its descendants
+    pub fn member_pids(&self) -> Vec<String> {
+        let mut pids = Vec::new();
+        collect_member_pids(&self.path, &mut pids);
+        pids
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        std::fs::read_to_string(self.path.join("cgroup.freeze")).is_ok_and(|it| it.trim() == "1")
+    }
+
+    /// Stop or resume all processes of the cgroup at once, with no chance to fork in between
+    pub fn set_frozen(&self, frozen: bool) -> Result<()> {
+        log(format!("Setting cgroup {} frozen: {}", self.name, frozen).as_str());
+        self.write_control("cgroup.freeze", if frozen { "1" } else { "0" })
+    }
+
+    /// Kill all processes of the cgroup and its descendants atomically, requires Linux 5.14
+    pub fn kill(&self) -> Result<()> {
+        log(format!("Killing cgroup {}", self.name).as_str());
+        self.write_control("cgroup.kill", "1")
+    }
+
+    /// Write a control file, falling back to a privileged write when not permitted
+    fn write_control(&self, file: &str, value: &str) -> Result<()> {
+        let path = self.path.join(file);
+        let result = std::fs::OpenOptions::new()
+            .write(true)
+            .open(&path)
+            .and_then(|mut file| file.write_all(value.as_bytes()));
+        match result {
+            Err(error) if error.kind() == ErrorKind::PermissionDenied => {
+                execute_privileged(format!("echo {} > {}", value, shell_quote(&path.to_string_lossy())))
+            }
+            Err(error) if error.kind() == ErrorKind::NotFound => Err(anyhow!(
+                "{} is not supported for cgroup {}, it may require a newer kernel",
+                file,
+                self.name
+            )),
+            Err(error) => Err(anyhow!(error)),
+            Ok(()) => Ok(()),
+        }
+    }
+}
+
+fn collect_member_pids(dir: &Path, pids: &mut Vec<String>) {
+    if let Ok(procs) = std::fs::read_to_string(dir.join("cgroup.procs")) {
+        pids.extend(procs.lines().map(|it| it.to_string()));
+    }
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.filter_map(|it| it.ok()) {
+        if entry.file_type().is_ok_and(|it| it.is_dir()) {
+            collect_member_pids(&entry.path(), pids);
+        }
+    }
+}
+
+/// Directory of a cgroup in the mounted filesystem.
+/// The mount may expose only a subtree of the hierarchy, e.g. in a container with its own cgroup namespace,
+/// so the cgroup path is taken relative to the mount's root.
+fn hierarchy_path(mount_point: &Path, mount_root: &str, name: &str) -> Result<PathBuf> {
+    let relative = match mount_root.trim_end_matches('/') {
+        "" => Some(name),
+        root => name
+            .strip_prefix(root)
+            .filter(|it| it.is_empty() || it.starts_with('/')),
+    };
+    match relative {
+        Some(relative) if !relative.starts_with("/..") => Ok(mount_point.join(relative.trim_start_matches('/'))),
+        _ => Err(anyhow!(
+            "cgroup {} is outside of the mounted cgroup hierarchy {}",
+            name,
+            mount_root
+        )),
+    }
+}
+
+/// Root within the hierarchy and mount point of the cgroup2 filesystem,
+/// mounted at `/sys/fs/cgroup` or `/sys/fs/cgroup/unified` on hybrid setups
+fn find_cgroup2_mount() -> Result<(String, PathBuf)> {
+    let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").context("failed to read mounts")?;
+    mountinfo
+        .lines()
+        .find_map(|line| {
+            // ID parent major:minor root mount-point options [optional fields] - fstype source super-options
+            let (fields, fs) = line.split_once(" - ")?;
+            match fs.split_whitespace().next()? {
+                "cgroup2" => {
+                    let mut fields = fields.split_whitespace().skip(3);
+                    Some((fields.next()?.to_string(), PathBuf::from(fields.next()?)))
+                }
+                _ => None,
+            }
+        })
+        .ok_or(anyhow!("cgroup v2 filesystem is not mounted"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hierarchy_path() {
+        let mount = Path::new("/sys/fs/cgroup");
+        let path = |root: &str, name: &str| hierarchy_path(mount, root, name).ok();
+        assert_eq!(
+            path("/", "/user.slice/session-2.scope"),
+            Some(PathBuf::from("/sys/fs/cgroup/user.slice/session-2.scope"))
+        );
+        assert_eq!(
+            path("/docker/abc", "/docker/abc/app"),
+            Some(PathBuf::from("/sys/fs/cgroup/app"))
+        );
+        assert_eq!(
+            path("/docker/abc", "/docker/abc"),
+            Some(PathBuf::from("/sys/fs/cgroup"))
+        );
+        assert_eq!(path("/docker/abc", "/docker/abcdef"), None);
+        assert_eq!(path("/docker/abc", "/user.slice"), None);
+        assert_eq!(path("/", "/../other.scope"), None);
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::action_menu::ConfirmPolicy;
+
+const USAGE: &str = "Usage: psycho [OPTIONS]
+
+Options:
+  --grace-period SECONDS              time to exit after SIGTERM before SIGKILL, 5 by default
+  --confirm always|destructive|never  when to ask before running an action, destructive by default
+  --config PATH                       config file, ~/.config/psycho-killer/config.toml by default
+  --dry-run                           show what actions would do without executing them
+  -h, --help                          print this help";
+
+#[derive(Debug, Clone)]
+pub struct CliArgs {
+    pub grace_period: Duration, // time given to a process to exit after SIGTERM before it gets SIGKILL
+    pub confirm: Option<ConfirmPolicy>, // confirmation policy of actions without one set in the config
+    pub config_path: Option<PathBuf>, // config file with user-defined actions
+    pub dry_run: bool,          // show what actions would do instead of executing them
+}
+
+impl Default for CliArgs {
+    fn default() -> Self {
+        CliArgs {
+            grace_period: Duration::from_secs(5),
+            confirm: None,
+            config_path: None,
+            dry_run: false,
+        }
+    }
+}
+
+pub fn parse_args() -> Result<CliArgs> {
+    let mut cli_args = CliArgs::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--grace-period" => {
+                let value = args.next().ok_or(anyhow!("missing value of --grace-period"))?;
+                let seconds: f64 = value.parse().context("grace period must be a number of seconds")?;
+                if !seconds.is_finite() || seconds < 0f64 {
+                    return Err(anyhow!("grace period must be a non-negative number of seconds"));
+                }
+                cli_args.grace_period = Duration::from_secs_f64(seconds);
+            }
+            "--confirm" => {
+                let value = args.next().ok_or(anyhow!("missing value of --confirm"))?;
+                let policy = ConfirmPolicy::parse(&value).ok_or(anyhow!(
+                    "confirmation policy must be one of: always, destructive, never"
+                ))?;
+                cli_args.confirm = Some(policy);
+            }
+            "--config" => {
+                let value = args.next().ok_or(anyhow!("missing value of --config"))?;
+                cli_args.config_path = Some(PathBuf::from(value));
+            }
+            "--dry-run" => cli_args.dry_run = true,
+            "--help" | "-h" => {
+                println!("{}", USAGE);
+                std::process::exit(0);
+            }
+            _ => return Err(anyhow!("unknown argument: {}, see psycho --help", arg)),
+        }
+    }
+    Ok(cli_args)
+}
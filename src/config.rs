@@ -0,0 +1,149 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::action_menu::{ConfirmPolicy, MenuAction, Operation};
+use crate::custom_action::{validate_template, CustomAction};
+use crate::escalation::EscalationBackend;
+use crate::protection::ProtectionRules;
+
+/// Settings read from `config.toml`
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    pub escalation: Option<String>, // sudo, doas, pkexec or run0, detected if not set
+    #[serde(default)]
+    pub protected: Vec<String>, // names or executable paths of processes guarded against disruptive signals
+    pub audit_log: Option<PathBuf>, // defaults to $XDG_STATE_HOME/psycho-killer/audit.log
+    #[serde(default)]
+    pub confirm: HashMap<String, String>, // confirmation policy of built-in actions by short name, e.g. "Kill forcefully"
+    #[serde(default)]
+    pub actions: Vec<ActionConfig>,
+}
+
+/// User-defined menu action, e.g. `name = "Thread dump"`, `command = "jstack {pid}"`
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ActionConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub superuser: bool, // run with the escalation backend, e.g. sudo
+    pub confirm: Option<String>, // always, destructive (default, when run as superuser) or never
+    #[serde(default)]
+    pub show_output: bool, // show standard output in the info popup
+}
+
+/// Default location: `$XDG_CONFIG_HOME/psycho-killer/config.toml` or `~/.config/psycho-killer/config.toml`
+pub fn default_config_path() -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("XDG_CONFIG_HOME").filter(|it| !it.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(std::env::var_os("HOME")?).join(".config"),
+    };
+    Some(config_dir.join("psycho-killer").join("config.toml"))
+}
+
+/// Load the config from an explicit path, or from the default one if it exists
+pub fn load_config(path: Option<&Path>) -> Result<Config> {
+    let path = match path {
+        Some(path) => path.to_path_buf(),
+        None => match default_config_path() {
+            Some(path) if path.exists() => path,
+            _ => return Ok(Config::default()),
+        },
+    };
+    let content = std::fs::read_to_string(&path).context(format!("failed to read {}", path.display()))?;
+    toml::from_str(&content).context(format!("failed to parse {}", path.display()))
+}
+
+impl Config {
+    pub fn escalation_backend(&self) -> Result<EscalationBackend> {
+        match &self.escalation {
+            None => Ok(EscalationBackend::detect()),
+            Some(value) => {
+                EscalationBackend::parse(value).ok_or(anyhow!("escalation must be one of: sudo, doas, pkexec, run0"))
+            }
+        }
+    }
+
+    pub fn protection_rules(&self) -> ProtectionRules {
+        ProtectionRules {
+            names: self.protected.clone(),
+        }
+    }
+
+    /// Set confirmation policies of built-in actions: from the config, otherwise the command-line default if given
+    pub fn apply_confirm_policies(&self, actions: &mut [MenuAction], default: Option<ConfirmPolicy>) -> Result<()> {
+        for name in self.confirm.keys() {
+            let confirmable = actions
+                .iter()
+                .any(|it| it.short_name() == name && it.operation.is_confirmable());
+            if !confirmable {
+                let known: Vec<String> = actions
+                    .iter()
+                    .filter(|it| it.operation.is_confirmable())
+                    .map(|it| format!("{:?}", it.short_name()))
+                    .collect();
+                return Err(anyhow!(
+                    "action {:?} in confirm is unknown or never asks for confirmation, expected one of: {}",
+                    name,
+                    known.join(", ")
+                ));
+            }
+        }
+        for action in actions {
+            match self.confirm.get(action.short_name()) {
+                Some(value) => action.confirm = parse_confirm_policy(value)?,
+                None => action.confirm = default.unwrap_or(action.confirm),
+            }
+        }
+        Ok(())
+    }
+
+    pub fn custom_menu_actions(
+        &self,
+        escalation: EscalationBackend,
+        default_confirm: Option<ConfirmPolicy>,
+    ) -> Result<Vec<MenuAction>> {
+        self.actions
+            .iter()
+            .map(|it| {
+                it.to_menu_action(escalation, default_confirm)
+                    .context(format!("invalid action {:?}", it.name))
+            })
+            .collect()
+    }
+}
+
+fn parse_confirm_policy(value: &str) -> Result<ConfirmPolicy> {
+    ConfirmPolicy::parse(value).ok_or(anyhow!("confirm must be one of: always, destructive, never"))
+}
+
+impl ActionConfig {
+    fn to_menu_action(
+        &self,
+        escalation: EscalationBackend,
+        default_confirm: Option<ConfirmPolicy>,
+    ) -> Result<MenuAction> {
+        validate_template(&self.command)?;
+        let confirm = match &self.confirm {
+            None => default_confirm.unwrap_or(ConfirmPolicy::Destructive),
+            Some(value) => parse_confirm_policy(value)?,
+        };
+        let name = match self.superuser {
+            true => format!("{}: {} {}", self.name, escalation.program(), self.command),
+            false => format!("{}: {}", self.name, self.command),
+        };
+        Ok(MenuAction {
+            name,
+            operation: Operation::Custom(CustomAction {
+                command: self.command.clone(),
+                superuser: self.superuser,
+                show_output: self.show_output,
+            }),
+            confirm,
+        })
+    }
+}
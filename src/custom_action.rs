@@ -0,0 +1,104 @@
+use anyhow::{anyhow, Result};
+
+use crate::action_menu::execute_shell_output;
+use crate::escalation::execute_privileged_output;
+use crate::strings::shell_quote;
+use crate::sysinfo::ProcessStat;
+
+pub const PLACEHOLDERS: [&str; 6] = ["pid", "exe", "cwd", "name", "user", "unit"];
+
+/// Shell command defined by the user, run once for every targeted process
+#[derive(Debug, Clone)]
+pub struct CustomAction {
+    pub command: String, // template with placeholders, e.g. "jstack {pid}"
+    pub superuser: bool,
+    pub show_output: bool,
+}
+
+impl CustomAction {
+    pub fn execute(&self, process: &ProcessStat) -> Result<String> {
+        let command = render_template(&self.command, process)?;
+        match self.superuser {
+            true => execute_privileged_output(command),
+            false => execute_shell_output(command),
+        }
+    }
+}
+
+/// Check that a template is well-formed and uses only known placeholders
+pub fn validate_template(template: &str) -> Result<()> {
+    substitute(template, |placeholder| match PLACEHOLDERS.contains(&placeholder) {
+        true => Ok(String::new()),
+        false => Err(anyhow!(
+            "unknown placeholder {{{}}}, supported ones are: {}",
+            placeholder,
+            PLACEHOLDERS.map(|it| format!("{{{}}}", it)).join(", ")
+        )),
+    })
+    .map(|_| ())
+}
+
+/// Replace placeholders with shell-quoted attributes of the process
+pub fn render_template(template: &str, process: &ProcessStat) -> Result<String> {
+    substitute(template, |placeholder| {
+        let value = match placeholder {
+            "pid" => process.pid.clone(),
+            "exe" => process.exe.clone(),
+            "cwd" => process.cwd.clone(),
+            "name" => process.name.clone(),
+            "user" => process.user_name.clone(),
+            "unit" => process
+                .systemd_unit
+                .as_ref()
+                .map(|it| it.name.clone())
+                .unwrap_or_default(),
+            _ => return Err(anyhow!("unknown placeholder {{{}}}", placeholder)),
+        };
+        Ok(shell_quote(&value))
+    })
+}
+
+/// Expand `{placeholder}` occurrences, `{{` and `}}` stand for literal braces
+fn substitute(template: &str, mut expand: impl FnMut(&str) -> Result<String>) -> Result<String> {
+    let mut result = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                result.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                result.push('}');
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => return Err(anyhow!("unclosed placeholder {{{}", placeholder)),
+                    }
+                }
+                result.push_str(expand(placeholder.trim())?.as_str());
+            }
+            _ => result.push(c),
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_template() {
+        assert!(validate_template("kill -HUP {pid}").is_ok());
+        assert!(validate_template("echo {{literal}} { exe }").is_ok());
+        assert!(validate_template("kill {pids}").is_err());
+        let error = validate_template("kill {pid").unwrap_err();
+        assert_eq!(error.to_string(), "unclosed placeholder {pid");
+    }
+}
@@ -0,0 +1,222 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::process::Command;
+use std::sync::{Mutex, MutexGuard};
+
+use anyhow::{anyhow, Result};
+use lazy_static::lazy_static;
+
+use crate::action_menu::run_command;
+use crate::logs::log;
+use crate::strings::shell_quote;
+
+/// Program used to run commands as Superuser
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum EscalationBackend {
+    #[default]
+    Sudo,
+    Doas,
+    Pkexec,
+    Run0,
+}
+
+impl EscalationBackend {
+    pub fn all() -> Vec<EscalationBackend> {
+        vec![
+            EscalationBackend::Sudo,
+            EscalationBackend::Doas,
+            EscalationBackend::Run0,
+            EscalationBackend::Pkexec,
+        ]
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::all().into_iter().find(|it| it.program() == value)
+    }
+
+    pub fn program(&self) -> &'static str {
+        match self {
+            EscalationBackend::Sudo => "sudo",
+            EscalationBackend::Doas => "doas",
+            EscalationBackend::Pkexec => "pkexec",
+            EscalationBackend::Run0 => "run0",
+        }
+    }
+
+    /// First backend installed on the system, sudo if none is found
+    pub fn detect() -> Self {
+        let path = std::env::var_os("PATH").unwrap_or_default();
+        Self::all()
+            .into_iter()
+            .find(|it| std::env::split_paths(&path).any(|dir| dir.join(it.program()).is_file()))
+            .unwrap_or_default()
+    }
+
+    /// Flag that makes the backend fail instead of asking for a password
+    fn non_interactive_flag(&self) -> Option<&'static str> {
+        match self {
+            EscalationBackend::Sudo => Some("-n"),
+            EscalationBackend::Doas => Some("-n"),
+            EscalationBackend::Run0 => Some("--no-ask-password"),
+            EscalationBackend::Pkexec => None,
+        }
+    }
+
+    /// Whether the password can be passed on standard input, so that it can be asked for in the TUI.
+    /// Other backends prompt on the terminal, which has to be restored first.
+    pub fn reads_password_from_stdin(&self) -> bool {
+        *self == EscalationBackend::Sudo
+    }
+}
+
+/// How privileged commands authenticate
+#[derive(Debug, Default, Clone)]
+pub enum AuthMode {
+    #[default]
+    NonInteractive, // fail with AuthenticationRequired when a password is needed
+    Password(String), // password typed in the TUI, passed on standard input
+    Terminal,         // the terminal is restored, so the backend can prompt on it
+}
+
+#[derive(Debug, Default)]
+struct EscalationState {
+    backend: EscalationBackend,
+    mode: AuthMode,
+}
+
+lazy_static! {
+    static ref ESCALATION_STATE: Mutex<EscalationState> = Mutex::new(EscalationState::default());
+}
+
+thread_local! {
+    // authentication of a background thread, unaffected by `reset_auth_mode` on the UI thread
+    static THREAD_AUTH_MODE: RefCell<Option<AuthMode>> = const { RefCell::new(None) };
+}
+
+fn escalation_state<'a>() -> MutexGuard<'a, EscalationState> {
+    ESCALATION_STATE.lock().unwrap()
+}
+
+#[derive(Debug)]
+pub struct AuthenticationRequired;
+
+impl fmt::Display for AuthenticationRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "authentication required")
+    }
+}
+
+impl std::error::Error for AuthenticationRequired {}
+
+pub fn is_authentication_required(error: &anyhow::Error) -> bool {
+    error.downcast_ref::<AuthenticationRequired>().is_some()
+}
+
+pub fn set_backend(backend: EscalationBackend) {
+    escalation_state().backend = backend;
+}
+
+pub fn backend() -> EscalationBackend {
+    escalation_state().backend
+}
+
+/// Authenticate privileged commands with a password until `reset_auth_mode` is called
+pub fn use_password(password: String) {
+    escalation_state().mode = AuthMode::Password(password);
+}
+
+/// Let privileged commands prompt on the restored terminal until `reset_auth_mode` is called
+pub fn use_terminal() {
+    escalation_state().mode = AuthMode::Terminal;
+}
+
+pub fn reset_auth_mode() {
+    escalation_state().mode = AuthMode::NonInteractive;
+}
+
+/// Authentication privileged commands of the current thread would use
+pub fn auth_mode() -> AuthMode {
+    THREAD_AUTH_MODE
+        .with(|it| it.borrow().clone())
+        .unwrap_or_else(|| escalation_state().mode.clone())
+}
+
+/// Keep authenticating privileged commands of the current thread the given way,
+/// e.g. with the password typed before a background operation was started
+pub fn use_thread_auth_mode(mode: AuthMode) {
+    THREAD_AUTH_MODE.with(|it| *it.borrow_mut() = Some(mode));
+}
+
+/// Check the password by refreshing the cached sudo credentials
+pub fn validate_password(password: &str) -> Result<()> {
+    let mut command = Command::new(backend().program());
+    command.args(["-S", "-p", "", "-v"]);
+    run_command(command, Some(format!("{}\n", password)), false)
+        .map(|_| ())
+        .map_err(|_| anyhow!("incorrect password"))
+}
+
+pub fn execute_privileged(cmd: String) -> Result<()> {
+    execute_privileged_output(cmd).map(|_| ())
+}
+
+/// Run a shell command as Superuser with the configured backend and return its standard output.
+/// Fails with `AuthenticationRequired` rather than prompting invisibly on the TUI screen.
+pub fn execute_privileged_output(cmd: String) -> Result<String> {
+    let (backend, mode) = (backend(), auth_mode());
+    let mut command = Command::new(backend.program());
+    let mut stdin_data = None;
+    match &mode {
+        AuthMode::NonInteractive => {
+            let flag = backend.non_interactive_flag().ok_or(anyhow!(AuthenticationRequired))?;
+            probe_non_interactive(backend, flag)?;
+            command.arg(flag);
+        }
+        AuthMode::Password(password) => {
+            command.args(["-S", "-p", ""]);
+            stdin_data = Some(format!("{}\n", password));
+        }
+        AuthMode::Terminal => {}
+    }
+    command.arg("sh").arg("-c").arg(&cmd);
+    log(format!("Executing command with {}: {:?}", backend.program(), cmd).as_str());
+    run_command(command, stdin_data, matches!(mode, AuthMode::Terminal))
+}
+
+/// Check that the backend runs commands without a password, before running the actual one.
+/// Only the backend's own output is inspected, so a command printing e.g. "authorization required" can't be mistaken for it.
+fn probe_non_interactive(backend: EscalationBackend, flag: &str) -> Result<()> {
+    let mut command = Command::new(backend.program());
+    command.args([flag, "true"]);
+    run_command(command, None, false).map(|_| ()).map_err(|error| {
+        let message = format!("{:#}", error).to_lowercase();
+        let auth_failed = [
+            "password is required",
+            "terminal is required",
+            "authentication required",
+            "authorization required",
+            "interactive authentication",
+        ]
+        .iter()
+        .any(|it| message.contains(it));
+        match auth_failed {
+            true => anyhow!(AuthenticationRequired),
+            false => error,
+        }
+    })
+}
+
+/// Command line `execute_privileged` would run, for dry runs
+pub fn describe_privileged(cmd: &str) -> String {
+    let backend = backend();
+    let flag = match auth_mode() {
+        AuthMode::NonInteractive => backend.non_interactive_flag().map(|it| format!("{} ", it)),
+        _ => None,
+    };
+    format!(
+        "{} {}sh -c {}",
+        backend.program(),
+        flag.unwrap_or_default(),
+        shell_quote(cmd)
+    )
+}
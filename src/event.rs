@@ -7,8 +7,10 @@ use std::{
 use anyhow::Result;
 use crossterm::event::{self, Event as CrosstermEvent, KeyEvent};
 
+use crate::kill_sequence::KillProgress;
+
 /// Terminal events.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Debug)]
 pub enum Event {
     /// Terminal tick.
     Tick,
@@ -16,6 +18,8 @@ pub enum Event {
     Key(KeyEvent),
     /// Terminal resize.
     Resize,
+    /// Update from a kill sequence running in the background.
+    KillProgress(KillProgress),
 }
 
 /// Terminal event handler.
@@ -24,18 +28,24 @@ pub struct EventHandler {
     sender: mpsc::Sender<Event>,
     receiver: mpsc::Receiver<Event>,
     suspended_store: Arc<Mutex<bool>>,
+    parked_store: Arc<Mutex<bool>>, // the reader acknowledged the suspension and no longer touches the terminal
     tick_rate: Duration,
 }
 
+/// Longest time the reader waits for input, so that it notices a suspension quickly
+const MAX_POLL_TIMEOUT: Duration = Duration::from_millis(50);
+
 impl EventHandler {
     pub fn new(tick_rate: u64) -> Self {
         let tick_rate = Duration::from_millis(tick_rate);
         let (sender, receiver) = mpsc::channel();
         let suspended_store = Arc::new(Mutex::new(false));
+        let parked_store = Arc::new(Mutex::new(false));
         Self {
             sender,
             receiver,
             suspended_store,
+            parked_store,
             tick_rate,
         }
     }
@@ -43,20 +53,28 @@ impl EventHandler {
     pub fn listen(self) -> Self {
         let sender = self.sender.clone();
         let suspended_store: Arc<Mutex<bool>> = self.suspended_store.clone();
+        let parked_store: Arc<Mutex<bool>> = self.parked_store.clone();
         thread::spawn(move || {
             let mut last_tick = Instant::now();
             loop {
                 let timeout = self
                     .tick_rate
                     .checked_sub(last_tick.elapsed())
-                    .unwrap_or(self.tick_rate);
+                    .unwrap_or(self.tick_rate)
+                    .min(MAX_POLL_TIMEOUT);
 
-                if Self::is_suspended(&suspended_store) {
-                    thread::sleep(Duration::from_millis(100));
+                // polling would read and buffer the input meant for the subprocess that took over the terminal
+                let suspended = Self::is_suspended(&suspended_store);
+                *parked_store.lock().unwrap() = suspended;
+                if suspended {
+                    thread::sleep(Duration::from_millis(10));
                     continue;
                 }
 
                 if event::poll(timeout).expect("unable to poll for event") {
+                    if Self::is_suspended(&suspended_store) {
+                        continue; // buffered before the suspension, discarded on resume
+                    }
                     match event::read().expect("unable to read event") {
                         CrosstermEvent::Key(e) => {
                             if !Self::is_suspended(&suspended_store.clone()) {
@@ -81,12 +99,21 @@ impl EventHandler {
         self
     }
 
+    pub fn sender(&self) -> mpsc::Sender<Event> {
+        self.sender.clone()
+    }
+
     pub fn next(&self) -> Result<Event> {
         Ok(self.receiver.recv()?)
     }
 
+    /// Stop reading the terminal, waiting until the reader is out of `poll`
     pub fn suspend(&self) {
         *self.suspended_store.lock().unwrap() = true;
+        let start = Instant::now();
+        while !*self.parked_store.lock().unwrap() && start.elapsed() < MAX_POLL_TIMEOUT * 10 {
+            thread::sleep(Duration::from_millis(5));
+        }
     }
 
     pub fn resume(&self) {
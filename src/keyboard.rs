@@ -14,6 +14,14 @@ pub fn update_on_key(app: &mut App, key_event: KeyEvent) {
         Browse => on_key_browse(app, key_event),
         ProcessFilter => on_key_process_filter(app, key_event),
         SignalPick => on_key_signal_pick(app, key_event),
+        SignalCatalogue => on_key_signal_catalogue(app, key_event),
+        TreeKillPreview => on_key_tree_kill_preview(app, key_event),
+        KillConfirm => on_key_kill_confirm(app, key_event),
+        OptionPick => on_key_option_pick(app, key_event),
+        ColumnPick => on_key_column_pick(app, key_event),
+        AffinityPick => on_key_affinity_pick(app, key_event),
+        PasswordPrompt => on_key_password_prompt(app, key_event),
+        ProtectedConfirm => on_key_protected_confirm(app, key_event),
         SystemStats => on_key_system_stats(app, key_event),
     }
 }
@@ -62,6 +70,8 @@ pub fn on_key_browse(app: &mut App, key_event: KeyEvent) {
         KeyCode::Char('f') if is_ctrl(key_event) => app.window_focus = ProcessFilter,
         KeyCode::Char('f') => app.window_focus = ProcessFilter,
         KeyCode::Char('/') | KeyCode::F(4) => app.window_focus = ProcessFilter,
+        KeyCode::Char(':') => app.start_port_filter(),
+        KeyCode::Char('@') => app.start_path_filter(),
         KeyCode::Char('?') => app.show_help(),
         KeyCode::F(5) => app.refresh_processes(),
         KeyCode::Char('r') => app.refresh_processes(),
@@ -70,7 +80,13 @@ pub fn on_key_browse(app: &mut App, key_event: KeyEvent) {
         KeyCode::Char('m') => app.set_process_ordering(Ordering::ByMemory),
         KeyCode::Char('c') => app.set_process_ordering(Ordering::ByCpu),
         KeyCode::Char('u') => app.set_process_ordering(Ordering::ByUptime),
+        KeyCode::Char('O') => app.set_process_ordering(Ordering::ByOomScore),
+        KeyCode::Char('I') => app.set_process_ordering(Ordering::ByDiskIo),
         KeyCode::Char('g') => app.toggle_group_by_exe(),
+        KeyCode::Char(' ') => app.toggle_mark(),
+        KeyCode::Char('p') => app.request_toggle_pause(),
+        KeyCode::Char('v') => app.open_column_pick(),
+        KeyCode::Char('H') => app.show_audit_history(),
         KeyCode::Enter => app.confirm_process(),
         _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
     };
@@ -115,6 +131,107 @@ pub fn on_key_signal_pick(app: &mut App, key_event: KeyEvent) {
     };
 }
 
+pub fn on_key_signal_catalogue(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc => app.window_focus = SignalPick,
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1),
+        KeyCode::PageDown => app.move_cursor(10),
+        KeyCode::PageUp => app.move_cursor(-10),
+        KeyCode::Home => app.move_cursor(-(app.signal_catalogue.len() as i32)),
+        KeyCode::End => app.move_cursor(app.signal_catalogue.len() as i32),
+        KeyCode::Enter => app.confirm_signal_catalogue(),
+        _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
+    };
+}
+
+pub fn on_key_tree_kill_preview(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc => app.window_focus = SignalPick,
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1),
+        KeyCode::PageDown => app.move_cursor(10),
+        KeyCode::PageUp => app.move_cursor(-10),
+        KeyCode::Char('o') => app.toggle_tree_kill_order(),
+        KeyCode::Enter => app.confirm_tree_kill(),
+        _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
+    };
+}
+
+pub fn on_key_kill_confirm(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc => app.window_focus = app.kill_confirm_origin,
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1),
+        KeyCode::PageDown => app.move_cursor(10),
+        KeyCode::PageUp => app.move_cursor(-10),
+        KeyCode::Enter => app.confirm_kill(),
+        _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
+    };
+}
+
+pub fn on_key_option_pick(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc => app.window_focus = SignalPick,
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1),
+        KeyCode::Home => app.move_cursor(-(app.option_pick_items.len() as i32)),
+        KeyCode::End => app.move_cursor(app.option_pick_items.len() as i32),
+        KeyCode::Enter => app.confirm_option_pick(),
+        _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
+    };
+}
+
+pub fn on_key_column_pick(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc | KeyCode::Char('v') => app.window_focus = Browse,
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1),
+        KeyCode::Enter | KeyCode::Char(' ') => app.toggle_extra_column(),
+        _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
+    };
+}
+
+pub fn on_key_affinity_pick(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc => app.window_focus = SignalPick,
+        KeyCode::Down | KeyCode::Char('j') => app.move_cursor(1),
+        KeyCode::Up | KeyCode::Char('k') => app.move_cursor(-1),
+        KeyCode::PageDown => app.move_cursor(10),
+        KeyCode::PageUp => app.move_cursor(-10),
+        KeyCode::Char(' ') => app.toggle_affinity_item(),
+        KeyCode::Char('a') => app.toggle_all_affinity_cores(),
+        KeyCode::Enter => app.confirm_affinity_pick(),
+        _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
+    };
+}
+
+pub fn on_key_password_prompt(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc => app.cancel_password_prompt(),
+        KeyCode::Enter => app.confirm_password(),
+        KeyCode::Backspace => {
+            app.password_input.pop();
+        }
+        KeyCode::Char('u') if is_ctrl(key_event) => app.password_input.clear(),
+        KeyCode::Char(c) => app.password_input.push(c),
+        _ => {} // don't log keys typed into the password
+    };
+}
+
+pub fn on_key_protected_confirm(app: &mut App, key_event: KeyEvent) {
+    match key_event.code {
+        KeyCode::Esc => app.window_focus = app.protected_confirm_origin,
+        KeyCode::Enter => app.confirm_protected(),
+        KeyCode::Backspace => {
+            app.protected_confirm_input.pop();
+        }
+        KeyCode::Char('u') if is_ctrl(key_event) => app.protected_confirm_input.clear(),
+        KeyCode::Char(c) => app.protected_confirm_input.push(c),
+        _ => log(format!("Unknown key event: {:?}", key_event).as_str()),
+    };
+}
+
 pub fn on_key_system_stats(app: &mut App, key_event: KeyEvent) {
     match key_event.code {
         KeyCode::Esc | KeyCode::Char('q') => app.quit(),
@@ -0,0 +1,245 @@
+use std::sync::mpsc::Sender;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::action_menu::kill_process;
+use crate::escalation::{self, AuthMode};
+use crate::event::Event;
+use crate::logs::log;
+use crate::signals::{ends_process, signal_name, KillError, ProcessHandle};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+pub const KILL_WAIT: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone)]
+pub enum KillProgress {
+    Step(String),
+    Finished {
+        outcomes: Vec<(String, KillOutcome)>,
+    },
+    TreeFinished {
+        signal: i32,
+        superuser: bool,
+        outcomes: Vec<(String, KillOutcome)>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum KillOutcome {
+    Terminated,       // exited within the grace period after SIGTERM
+    Killed,           // exited after SIGKILL
+    AlreadyGone,      // there was no process to signal
+    PermissionDenied, // process belongs to another user
+    StillAlive,       // survived SIGKILL, e.g. stuck in uninterruptible sleep
+    Signalled,        // signal was delivered and the process kept running
+    Failed(String),
+}
+
+/// Run SIGTERM, wait, SIGKILL sequence in the background, reporting progress through the event channel.
+/// Targets are PIDs with their start times, so that a process reusing the PID in the meantime is left alone.
+/// Privileged steps authenticate the given way, since the UI thread resets its authentication right after.
+pub fn spawn_kill_sequence(
+    targets: Vec<(String, Option<u64>)>,
+    grace_period: Duration,
+    superuser: bool,
+    auth_mode: AuthMode,
+    sender: Sender<Event>,
+) {
+    spawn_reporting(superuser, auth_mode, sender, move |report| {
+        let outcomes = run_kill_sequence(&targets, grace_period, superuser, report);
+        KillProgress::Finished { outcomes }
+    });
+}
+
+/// Signal a process tree one depth level at a time in the background, starting with the deepest descendants.
+/// Each level gets the grace period to exit before its parents are signalled, unless the signal doesn't end processes.
+pub fn spawn_tree_kill(
+    levels: Vec<Vec<(String, Option<u64>)>>,
+    signal: i32,
+    grace_period: Duration,
+    superuser: bool,
+    auth_mode: AuthMode,
+    sender: Sender<Event>,
+) {
+    spawn_reporting(superuser, auth_mode, sender, move |report| {
+        let outcomes = run_tree_kill(&levels, signal, grace_period, superuser, report);
+        KillProgress::TreeFinished {
+            signal,
+            superuser,
+            outcomes,
+        }
+    });
+}
+
+/// Run the work in the background, passing its steps and final result through the event channel
+fn spawn_reporting(
+    superuser: bool,
+    auth_mode: AuthMode,
+    sender: Sender<Event>,
+    work: impl FnOnce(&dyn Fn(String)) -> KillProgress + Send + 'static,
+) {
+    let run = move || {
+        let report = |message: String| {
+            log(message.as_str());
+            sender.send(Event::KillProgress(KillProgress::Step(message))).ok();
+        };
+        let finished = work(&report);
+        log(format!("Kill sequence finished: {:?}", finished).as_str());
+        sender.send(Event::KillProgress(finished)).ok();
+    };
+    match auth_mode {
+        // the backend prompts on the terminal, which is handed over only until this call returns
+        AuthMode::Terminal if superuser => run(),
+        _ => {
+            thread::spawn(move || {
+                escalation::use_thread_auth_mode(auth_mode);
+                run()
+            });
+        }
+    }
+}
+
+/// Terminate all processes at once, so that members of a group get the same grace period
+fn run_kill_sequence(
+    targets: &[(String, Option<u64>)],
+    grace_period: Duration,
+    superuser: bool,
+    report: impl Fn(String),
+) -> Vec<(String, KillOutcome)> {
+    let mut outcomes: Vec<(String, KillOutcome)> = Vec::new();
+
+    let pids: Vec<String> = targets.iter().map(|(pid, _)| pid.clone()).collect();
+    report(format!("Sending SIGTERM to {}", describe_pids(&pids)));
+    let mut pending: Vec<ProcessHandle> = Vec::new();
+    for (pid, start_time) in targets {
+        let result = ProcessHandle::open(pid, *start_time)
+            .and_then(|handle| kill_process(&handle, libc::SIGTERM, superuser).map(|_| handle));
+        match result {
+            Ok(handle) => pending.push(handle),
+            Err(err) => outcomes.push((pid.clone(), outcome_of_error(err))),
+        }
+    }
+    if pending.is_empty() {
+        return outcomes;
+    }
+    report(format!(
+        "Waiting up to {}s for {} to exit",
+        grace_period.as_secs_f64(),
+        describe_handles(&pending)
+    ));
+    let survivors = wait_for_exit(pending, grace_period, &mut outcomes, KillOutcome::Terminated);
+    if survivors.is_empty() {
+        return outcomes;
+    }
+
+    report(format!(
+        "{} survived SIGTERM, sending SIGKILL",
+        describe_handles(&survivors)
+    ));
+    let mut pending: Vec<ProcessHandle> = Vec::new();
+    for handle in survivors {
+        match kill_process(&handle, libc::SIGKILL, superuser) {
+            Ok(()) => pending.push(handle),
+            Err(KillError::NoSuchProcess) => outcomes.push((handle.pid.clone(), KillOutcome::Terminated)), // exited in the meantime
+            Err(err) => outcomes.push((handle.pid.clone(), outcome_of_error(err))),
+        }
+    }
+    let survivors = wait_for_exit(pending, KILL_WAIT, &mut outcomes, KillOutcome::Killed);
+    for handle in survivors {
+        outcomes.push((handle.pid.clone(), KillOutcome::StillAlive));
+    }
+    outcomes
+}
+
+fn run_tree_kill(
+    levels: &[Vec<(String, Option<u64>)>],
+    signal: i32,
+    grace_period: Duration,
+    superuser: bool,
+    report: impl Fn(String),
+) -> Vec<(String, KillOutcome)> {
+    let mut outcomes: Vec<(String, KillOutcome)> = Vec::new();
+    for level in levels {
+        let pids: Vec<String> = level.iter().map(|(pid, _)| pid.clone()).collect();
+        report(format!("Sending {} to {}", signal_name(signal), describe_pids(&pids)));
+        let mut pending: Vec<ProcessHandle> = Vec::new();
+        for (pid, start_time) in level {
+            let result = ProcessHandle::open(pid, *start_time)
+                .and_then(|handle| kill_process(&handle, signal, superuser).map(|_| handle));
+            match result {
+                Ok(handle) => pending.push(handle),
+                Err(err) => outcomes.push((pid.clone(), outcome_of_error(err))),
+            }
+        }
+        if !ends_process(signal) || pending.is_empty() {
+            outcomes.extend(pending.into_iter().map(|it| (it.pid.clone(), KillOutcome::Signalled)));
+            continue;
+        }
+        report(format!(
+            "Waiting up to {}s for {} to exit",
+            grace_period.as_secs_f64(),
+            describe_handles(&pending)
+        ));
+        let survivors = wait_for_exit(pending, grace_period, &mut outcomes, KillOutcome::Terminated);
+        if !survivors.is_empty() {
+            report(format!("{} still running, continuing", describe_handles(&survivors)));
+        }
+        outcomes.extend(survivors.into_iter().map(|it| (it.pid.clone(), KillOutcome::Signalled)));
+    }
+    outcomes
+}
+
+fn describe_handles(handles: &[ProcessHandle]) -> String {
+    describe_pids(&handles.iter().map(|it| it.pid.clone()).collect::<Vec<String>>())
+}
+
+fn describe_pids(pids: &[String]) -> String {
+    match pids.len() {
+        1 => format!("process {}", pids[0]),
+        _ => format!("{} processes: {}", pids.len(), pids.join(", ")),
+    }
+}
+
+fn outcome_of_error(err: KillError) -> KillOutcome {
+    match err {
+        KillError::NoSuchProcess | KillError::ProcessReplaced => KillOutcome::AlreadyGone,
+        KillError::PermissionDenied => KillOutcome::PermissionDenied,
+        _ => KillOutcome::Failed(err.to_string()),
+    }
+}
+
+/// Wait until all processes exit or the timeout passes, recording the exited ones. Returns the ones still alive.
+fn wait_for_exit(
+    handles: Vec<ProcessHandle>,
+    timeout: Duration,
+    outcomes: &mut Vec<(String, KillOutcome)>,
+    exit_outcome: KillOutcome,
+) -> Vec<ProcessHandle> {
+    let start = Instant::now();
+    let mut alive = handles;
+    loop {
+        let (still_alive, exited): (Vec<ProcessHandle>, Vec<ProcessHandle>) =
+            alive.into_iter().partition(|handle| handle.is_alive());
+        for handle in exited {
+            outcomes.push((handle.pid.clone(), exit_outcome.clone()));
+        }
+        alive = still_alive;
+        if alive.is_empty() || start.elapsed() >= timeout {
+            return alive;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// Process is alive if it exists and is not a zombie waiting to be reaped
+pub fn is_process_alive(pid: &str) -> bool {
+    let stat = match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => stat,
+        Err(_) => return false,
+    };
+    // state comes right after the command name, which is enclosed in parentheses and may contain spaces
+    let state = stat
+        .rsplit_once(')')
+        .and_then(|(_, rest)| rest.split_whitespace().next());
+    !matches!(state, Some("Z") | Some("X") | Some("x"))
+}
@@ -2,22 +2,43 @@ mod action_menu;
 mod app;
 mod app_logic;
 mod appdata;
+mod audit;
+mod cgroup;
+mod cli;
+mod config;
+mod custom_action;
+mod escalation;
 mod event;
 mod keyboard;
+mod kill_sequence;
 mod logs;
 mod numbers;
+mod oom;
+mod path_holders;
+mod ports;
+mod process_tree;
+mod protection;
+mod restart;
+mod scheduling;
+mod signals;
+mod smaps;
 mod strings;
 mod sysinfo;
 mod sysinfo_render;
+mod systemd;
 mod tui;
 mod ui;
 
 use anyhow::{Context, Result};
 
 use crate::app::App;
+use crate::cli::parse_args;
+use crate::config::load_config;
 
 fn main() -> Result<()> {
-    let mut app = App::new();
+    let args = parse_args().context("invalid arguments")?;
+    let config = load_config(args.config_path.as_deref()).context("invalid config")?;
+    let mut app = App::new(args, &config).context("invalid config")?;
     app.run().context("app failed")?;
     Ok(())
 }
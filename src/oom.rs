@@ -0,0 +1,51 @@
+use std::io::{ErrorKind, Write};
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::escalation::execute_privileged;
+use crate::logs::log;
+
+pub const OOM_SCORE_ADJ_MIN: i32 = -1000;
+pub const OOM_SCORE_ADJ_MAX: i32 = 1000;
+
+/// Badness score the kernel OOM killer uses to pick its victim (higher is killed first), and its adjustment
+pub fn read_oom_score(pid: &str) -> Result<(i32, i32)> {
+    let score = read_proc_number(pid, "oom_score")?;
+    let score_adj = read_proc_number(pid, "oom_score_adj")?;
+    Ok((score, score_adj))
+}
+
+fn read_proc_number(pid: &str, name: &str) -> Result<i32> {
+    let content = std::fs::read_to_string(format!("/proc/{}/{}", pid, name)).context(format!("reading {}", name))?;
+    content.trim().parse::<i32>().context(format!("parsing {}", name))
+}
+
+/// Privileged fallback of `set_oom_score_adj`
+pub fn oom_score_adj_command(pid: &str, score_adj: i32) -> String {
+    format!("echo {} > /proc/{}/oom_score_adj", score_adj, pid)
+}
+
+/// Write `oom_score_adj`, falling back to a privileged write when lowering it is not permitted
+pub fn set_oom_score_adj(pid: &str, score_adj: i32) -> Result<()> {
+    if !(OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(&score_adj) {
+        return Err(anyhow!(
+            "OOM score adjustment must be between {} and {}",
+            OOM_SCORE_ADJ_MIN,
+            OOM_SCORE_ADJ_MAX
+        ));
+    }
+    let path = format!("/proc/{}/oom_score_adj", pid);
+    log(format!("Setting OOM score adjustment {} of PID {}", score_adj, pid).as_str());
+    let result = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .and_then(|mut file| file.write_all(score_adj.to_string().as_bytes()));
+    match result {
+        Err(error) if error.kind() == ErrorKind::PermissionDenied => {
+            execute_privileged(oom_score_adj_command(pid, score_adj))
+        }
+        Err(error) if error.kind() == ErrorKind::NotFound => Err(anyhow!("process {} no longer exists", pid)),
+        Err(error) => Err(anyhow!(error)),
+        Ok(()) => Ok(()),
+    }
+}
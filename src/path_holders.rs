@@ -0,0 +1,151 @@
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Processes keeping a path busy, found by scanning `/proc` like `fuser` or `lsof` do
+#[derive(Debug, Default)]
+pub struct PathHolders {
+    pub reasons: HashMap<String, Vec<String>>, // PID to what holds the path, e.g. "cwd", "fd 3", "mmap"
+    pub uninspected: usize,                    // processes whose open files can't be read, e.g. of other users
+}
+
+/// Path written as `@/mnt/usb` in the filter
+pub fn parse_path_word(word: &str) -> Option<PathBuf> {
+    let path = word.strip_prefix('@')?;
+    if path.is_empty() {
+        return None;
+    }
+    match path.strip_prefix("~/") {
+        Some(rest) => Some(PathBuf::from(std::env::var_os("HOME")?).join(rest)),
+        None => Some(PathBuf::from(path)),
+    }
+}
+
+/// Find processes using the file, anything inside the directory, or anything on the mount point
+pub fn find_path_holders(path: &Path) -> Result<PathHolders> {
+    let path = path
+        .canonicalize()
+        .context(format!("no such path {}", path.display()))?;
+    let mut holders = PathHolders::default();
+    for entry in std::fs::read_dir("/proc").context("failed to list processes")? {
+        let Ok(entry) = entry else { continue };
+        let pid = entry.file_name().to_string_lossy().to_string();
+        if pid.parse::<u32>().is_err() {
+            continue;
+        }
+        let (reasons, complete) = process_path_reasons(&pid, &path);
+        if !complete && reasons.is_empty() {
+            holders.uninspected += 1;
+        }
+        if !reasons.is_empty() {
+            holders.reasons.insert(pid, reasons);
+        }
+    }
+    Ok(holders)
+}
+
+/// Ways the process uses the path, and whether all of its files could be inspected
+fn process_path_reasons(pid: &str, path: &Path) -> (Vec<String>, bool) {
+    let mut reasons = Vec::new();
+    let mut complete = true;
+    for (link, reason) in [("cwd", "cwd"), ("root", "root"), ("exe", "exe")] {
+        match std::fs::read_link(format!("/proc/{}/{}", pid, link)) {
+            Ok(target) if is_within(&target, path) => reasons.push(reason.to_string()),
+            Ok(_) => {}
+            Err(error) if error.kind() == ErrorKind::PermissionDenied => complete = false,
+            Err(_) => {} // e.g. kernel threads have no executable
+        }
+    }
+    match std::fs::read_dir(format!("/proc/{}/fd", pid)) {
+        Ok(fds) => {
+            let mut fd_reasons: Vec<(u32, String)> = fds
+                .filter_map(|it| it.ok())
+                .filter(|fd| {
+                    std::fs::read_link(fd.path())
+                        .map(|target| is_within(&target, path))
+                        .unwrap_or(false)
+                })
+                .filter_map(|fd| fd.file_name().to_string_lossy().parse::<u32>().ok())
+                .map(|fd| (fd, format!("fd {}", fd)))
+                .collect();
+            fd_reasons.sort();
+            reasons.extend(fd_reasons.into_iter().map(|(_, reason)| reason));
+        }
+        Err(error) if error.kind() == ErrorKind::PermissionDenied => complete = false,
+        Err(_) => {}
+    }
+    match std::fs::read_to_string(format!("/proc/{}/maps", pid)) {
+        Ok(maps) => {
+            let mapped = maps
+                .lines()
+                .filter_map(mapped_path)
+                .any(|mapped| is_within(Path::new(mapped), path));
+            if mapped {
+                reasons.push("mmap".to_string());
+            }
+        }
+        Err(error) if error.kind() == ErrorKind::PermissionDenied => complete = false,
+        Err(_) => {}
+    }
+    (reasons, complete)
+}
+
+/// Path of a line of `/proc/PID/maps`, which may contain spaces: address perms offset dev inode pathname
+fn mapped_path(line: &str) -> Option<&str> {
+    let path = line.splitn(6, ' ').nth(5)?.trim_start();
+    match path.is_empty() {
+        true => None,
+        false => Some(path),
+    }
+}
+
+/// Whether the link target is the path itself or lies beneath it, also for deleted files
+fn is_within(target: &Path, path: &Path) -> bool {
+    let target_str = target.to_string_lossy();
+    let target = Path::new(target_str.trim_end_matches(" (deleted)"));
+    target.starts_with(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_path_word() {
+        assert_eq!(parse_path_word("@/var/log"), Some(PathBuf::from("/var/log")));
+        assert_eq!(parse_path_word("@relative/dir"), Some(PathBuf::from("relative/dir")));
+        assert_eq!(parse_path_word("@"), None);
+        assert_eq!(parse_path_word("/var/log"), None);
+        if let Some(home) = std::env::var_os("HOME") {
+            assert_eq!(
+                parse_path_word("@~/notes.txt"),
+                Some(PathBuf::from(home).join("notes.txt"))
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_within() {
+        let path = Path::new("/mnt/data");
+        assert!(is_within(Path::new("/mnt/data"), path));
+        assert!(is_within(Path::new("/mnt/data/db/file.log"), path));
+        assert!(is_within(Path::new("/mnt/data/file.log (deleted)"), path));
+        assert!(!is_within(Path::new("/mnt/database"), path));
+        assert!(!is_within(Path::new("/mnt"), path));
+    }
+
+    #[test]
+    fn test_mapped_path() {
+        let line = "7f3c1a200000-7f3c1a228000 r--p 00000000 08:01 1835009                    /usr/lib/libc.so.6";
+        assert_eq!(mapped_path(line), Some("/usr/lib/libc.so.6"));
+        let line = "7f3c1a200000-7f3c1a228000 r--p 00000000 08:01 1835010                    /opt/My App/lib.so";
+        assert_eq!(mapped_path(line), Some("/opt/My App/lib.so"));
+        let line = "7f3c1a200000-7f3c1a228000 rw-p 00000000 00:00 0                          [heap]";
+        assert_eq!(mapped_path(line), Some("[heap]"));
+        let line = "7f3c1a200000-7f3c1a228000 rw-p 00000000 00:00 0 ";
+        assert_eq!(mapped_path(line), None);
+        assert_eq!(mapped_path("7f3c1a200000-7f3c1a228000 rw-p 00000000 00:00 0"), None);
+    }
+}
@@ -0,0 +1,131 @@
+use std::collections::HashSet;
+
+use anyhow::{Context, Result};
+
+const TCP_LISTEN: &str = "0A";
+
+/// Sockets bound to the port, read from `/proc/net/{tcp,tcp6,udp,udp6}`
+#[derive(Debug, Default)]
+pub struct PortOwners {
+    pub pids: HashSet<String>,
+    pub unresolved_sockets: usize, // sockets whose owner can't be seen, e.g. held by another user's process
+}
+
+/// Port written as `:8080` in the filter
+pub fn parse_port_word(word: &str) -> Option<u16> {
+    word.strip_prefix(':')?.parse::<u16>().ok()
+}
+
+/// Processes holding a listening TCP socket or a bound UDP socket on the local port
+pub fn find_port_owners(port: u16) -> Result<PortOwners> {
+    let mut inodes: HashSet<u64> = HashSet::new();
+    for table in ["tcp", "tcp6", "udp", "udp6"] {
+        let content = match std::fs::read_to_string(format!("/proc/net/{}", table)) {
+            Ok(content) => content,
+            Err(_) => continue, // e.g. IPv6 disabled
+        };
+        inodes.extend(parse_socket_table(&content, port, table.starts_with("tcp")));
+    }
+    inodes.remove(&0);
+    if inodes.is_empty() {
+        return Ok(PortOwners::default());
+    }
+
+    let mut owners = PortOwners::default();
+    let mut found_inodes: HashSet<u64> = HashSet::new();
+    for entry in std::fs::read_dir("/proc").context("failed to list processes")? {
+        let Ok(entry) = entry else { continue };
+        let pid = entry.file_name().to_string_lossy().to_string();
+        if pid.parse::<u32>().is_err() {
+            continue;
+        }
+        let Ok(fds) = std::fs::read_dir(format!("/proc/{}/fd", pid)) else {
+            continue; // not permitted to inspect other users' processes
+        };
+        for fd in fds.filter_map(|it| it.ok()) {
+            let Some(inode) = std::fs::read_link(fd.path())
+                .ok()
+                .and_then(|it| socket_inode(&it.to_string_lossy()))
+            else {
+                continue;
+            };
+            if inodes.contains(&inode) {
+                found_inodes.insert(inode);
+                owners.pids.insert(pid.clone());
+            }
+        }
+    }
+    owners.unresolved_sockets = inodes.difference(&found_inodes).count();
+    Ok(owners)
+}
+
+/// Inodes of sockets bound to the port, only listening ones for TCP
+fn parse_socket_table(content: &str, port: u16, listening_only: bool) -> Vec<u64> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            // sl local_address rem_address st tx_queue:rx_queue tr:tm->when retrnsmt uid timeout inode
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            if parts.len() < 10 {
+                return None;
+            }
+            let local_port = u16::from_str_radix(parts[1].rsplit(':').next()?, 16).ok()?;
+            if local_port != port || (listening_only && parts[3] != TCP_LISTEN) {
+                return None;
+            }
+            parts[9].parse::<u64>().ok()
+        })
+        .collect()
+}
+
+/// Inode of a `socket:[12345]` file descriptor link
+fn socket_inode(link: &str) -> Option<u64> {
+    link.strip_prefix("socket:[")?.strip_suffix(']')?.parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000:1F90 00000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 662 1 00000000120a42a2 100 0 0 10 0
+   1: 0100007F:1F90 0100007F:DE88 01 00000000:00000000 00:00000000 00000000  1000        0 43746 2 00000000d1411f33 20 4 32 18 -1
+   2: 0100007F:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 945 1 00000000bdb0be62 100 0 0 10 0
+";
+    const TCP6: &str = "  sl  local_address                         remote_address                        st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 00000000000000000000000000000000:1F90 00000000000000000000000000000000:0000 0A 00000000:00000000 00:00000000 00000000  1000        0 1234 1 0000000000000000 100 0 0 10 0
+";
+    const UDP: &str = "   sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode ref pointer drops
+  100: 00000000:14E9 00000000:0000 07 00000000:00000000 00:00000000 00000000   104        0 2210 2 0000000000000000 0
+";
+
+    #[test]
+    fn test_parse_socket_table() {
+        assert_eq!(parse_socket_table(TCP, 8080, true), vec![662]);
+        assert_eq!(parse_socket_table(TCP, 8080, false), vec![662, 43746]);
+        assert_eq!(parse_socket_table(TCP, 22, true), vec![945]);
+        assert_eq!(parse_socket_table(TCP, 443, true), Vec::<u64>::new());
+        assert_eq!(parse_socket_table(TCP6, 8080, true), vec![1234]);
+        assert_eq!(parse_socket_table(UDP, 5353, false), vec![2210]);
+        assert_eq!(
+            parse_socket_table("header\n  0: truncated line\n", 8080, false),
+            Vec::<u64>::new()
+        );
+    }
+
+    #[test]
+    fn test_socket_inode() {
+        assert_eq!(socket_inode("socket:[12345]"), Some(12345));
+        assert_eq!(socket_inode("pipe:[12345]"), None);
+        assert_eq!(socket_inode("socket:[12345"), None);
+        assert_eq!(socket_inode("/dev/null"), None);
+    }
+
+    #[test]
+    fn test_parse_port_word() {
+        assert_eq!(parse_port_word(":8080"), Some(8080));
+        assert_eq!(parse_port_word("8080"), None);
+        assert_eq!(parse_port_word(":70000"), None);
+    }
+}
@@ -0,0 +1,105 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::sysinfo::ProcessStat;
+
+#[derive(Debug, Clone)]
+pub struct TreeNode {
+    pub depth: usize,
+    pub process: ProcessStat,
+}
+
+/// Collect processes with all their descendants, in depth-first order, parents before children
+pub fn collect_subtree(processes: &[ProcessStat], root_pids: &[String]) -> Vec<TreeNode> {
+    let mut children: HashMap<&str, Vec<&ProcessStat>> = HashMap::new();
+    for process in processes {
+        if let Some(parent_pid) = &process.parent_pid {
+            children.entry(parent_pid.as_str()).or_default().push(process);
+        }
+    }
+    for siblings in children.values_mut() {
+        siblings.sort_by_key(|it| it.pid_num);
+    }
+
+    let mut nodes: Vec<TreeNode> = Vec::new();
+    let mut visited: HashSet<&str> = HashSet::new();
+    for root_pid in root_pids {
+        // roots nested in another root's subtree are visited along the way
+        if has_ancestor_in(processes, root_pid, root_pids) {
+            continue;
+        }
+        if let Some(root) = processes.iter().find(|it| it.pid == *root_pid) {
+            visit(root, 0, &children, &mut visited, &mut nodes);
+        }
+    }
+    nodes
+}
+
+fn has_ancestor_in(processes: &[ProcessStat], pid: &str, ancestor_pids: &[String]) -> bool {
+    let parent_of = |pid: &str| {
+        processes
+            .iter()
+            .find(|it| it.pid == pid)
+            .and_then(|it| it.parent_pid.clone())
+    };
+    let mut current = parent_of(pid);
+    for _ in 0..processes.len() {
+        match current {
+            Some(parent_pid) if ancestor_pids.contains(&parent_pid) => return true,
+            Some(parent_pid) => current = parent_of(&parent_pid),
+            None => return false,
+        }
+    }
+    false
+}
+
+fn visit<'a>(
+    process: &'a ProcessStat,
+    depth: usize,
+    children: &HashMap<&str, Vec<&'a ProcessStat>>,
+    visited: &mut HashSet<&'a str>,
+    nodes: &mut Vec<TreeNode>,
+) {
+    if !visited.insert(process.pid.as_str()) {
+        return;
+    }
+    nodes.push(TreeNode {
+        depth,
+        process: process.clone(),
+    });
+    for child in children.get(process.pid.as_str()).into_iter().flatten() {
+        visit(child, depth + 1, children, visited, nodes);
+    }
+}
+
+/// Nodes grouped by depth, the deepest descendants first and the roots last
+pub fn children_first_levels(nodes: &[TreeNode]) -> Vec<Vec<&TreeNode>> {
+    let max_depth = nodes.iter().map(|it| it.depth).max().unwrap_or(0);
+    (0..=max_depth)
+        .rev()
+        .map(|depth| nodes.iter().filter(|it| it.depth == depth).collect::<Vec<&TreeNode>>())
+        .filter(|level| !level.is_empty())
+        .collect()
+}
+
+/// PIDs ordered so that the deepest descendants are signalled first and the roots last
+pub fn children_first_pids(nodes: &[TreeNode]) -> Vec<String> {
+    children_first_levels(nodes)
+        .concat()
+        .iter()
+        .map(|it| it.process.pid.clone())
+        .collect()
+}
+
+pub fn format_tree(nodes: &[TreeNode]) -> String {
+    nodes
+        .iter()
+        .map(|node| {
+            let indent = match node.depth {
+                0 => String::new(),
+                depth => format!("{}└─ ", "   ".repeat(depth - 1)),
+            };
+            format!("{}{} {}", indent, node.process.pid, node.process.display_name)
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
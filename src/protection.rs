@@ -0,0 +1,129 @@
+use lazy_static::lazy_static;
+
+use crate::sysinfo::ProcessStat;
+
+/// Display servers and compositors, killing them takes down the whole graphical session
+const DISPLAY_SERVERS: [&str; 13] = [
+    "Xorg",
+    "X",
+    "Xwayland",
+    "gnome-shell",
+    "kwin_wayland",
+    "kwin_x11",
+    "sway",
+    "Hyprland",
+    "weston",
+    "labwc",
+    "wayfire",
+    "river",
+    "niri",
+];
+
+/// Programs that run psycho as another user, standing between it and the shell it was started from
+const ESCALATION_WRAPPERS: [&str; 5] = ["sudo", "doas", "su", "pkexec", "run0"];
+
+lazy_static! {
+    static ref PARENT_SHELL_PID: u32 = find_parent_shell();
+}
+
+/// Reason why a process is guarded against signals that could take down the system or the user's session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Protection {
+    Init,
+    KernelThread,
+    Itself,
+    ParentShell,
+    SessionLeader,
+    DisplayServer,
+    Configured(String), // name or executable path listed in the config
+}
+
+impl Protection {
+    pub fn describe(&self) -> String {
+        match self {
+            Protection::Init => "the init process".to_string(),
+            Protection::KernelThread => "a kernel thread".to_string(),
+            Protection::Itself => "psycho itself".to_string(),
+            Protection::ParentShell => "the shell psycho was started from".to_string(),
+            Protection::SessionLeader => "the leader of psycho's session".to_string(),
+            Protection::DisplayServer => "a display server".to_string(),
+            Protection::Configured(name) => format!("protected in the config as {:?}", name),
+        }
+    }
+
+    /// Signals to these processes are refused outright, others can be confirmed by typing the process name
+    pub fn is_refused(&self) -> bool {
+        matches!(self, Protection::Init | Protection::KernelThread | Protection::Itself)
+    }
+}
+
+/// Processes guarded in addition to the built-in ones, matched by name or executable path
+#[derive(Debug, Default, Clone)]
+pub struct ProtectionRules {
+    pub names: Vec<String>,
+}
+
+impl ProtectionRules {
+    pub fn check(&self, process: &ProcessStat) -> Option<Protection> {
+        let own_pid = std::process::id();
+        let parent_pid = *PARENT_SHELL_PID;
+        let session_id = unsafe { libc::getsid(0) } as u32;
+        match process.pid_num {
+            1 => Some(Protection::Init),
+            _ if process.kernel_thread => Some(Protection::KernelThread),
+            pid if pid == own_pid => Some(Protection::Itself),
+            pid if pid == parent_pid => Some(Protection::ParentShell),
+            pid if pid == session_id => Some(Protection::SessionLeader),
+            _ if DISPLAY_SERVERS.contains(&process.name.as_str()) => Some(Protection::DisplayServer),
+            _ => self
+                .names
+                .iter()
+                .find(|it| **it == process.name || **it == process.exe)
+                .map(|it| Protection::Configured(it.clone())),
+        }
+    }
+}
+
+/// First ancestor that isn't an escalation wrapper, e.g. the shell behind `sudo psycho`
+fn find_parent_shell() -> u32 {
+    let mut pid = unsafe { libc::getppid() } as u32;
+    for _ in 0..ESCALATION_WRAPPERS.len() {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).unwrap_or_default();
+        match parse_name_and_parent(&stat) {
+            Some((name, parent)) if ESCALATION_WRAPPERS.contains(&name) && parent > 1 => pid = parent,
+            _ => break,
+        }
+    }
+    pid
+}
+
+/// Command name and parent PID from the content of `/proc/PID/stat`
+fn parse_name_and_parent(stat: &str) -> Option<(&str, u32)> {
+    // the name is enclosed in parentheses and may contain spaces, the state and parent PID follow it
+    let (head, rest) = stat.rsplit_once(')')?;
+    let name = head.split_once('(')?.1;
+    let parent = rest.split_whitespace().nth(1)?.parse().ok()?;
+    Some((name, parent))
+}
+
+/// Signals that can't terminate, stop or otherwise disrupt a process
+pub fn is_harmless_signal(signal: i32) -> bool {
+    matches!(
+        signal,
+        0 | libc::SIGCONT | libc::SIGWINCH | libc::SIGURG | libc::SIGCHLD
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_name_and_parent() {
+        let stat = "4242 (sudo) S 4100 4242 4100 34816 4242 4194560 101 0 0 0 0 0 0 0 20 0 1 0 123456";
+        assert_eq!(parse_name_and_parent(stat), Some(("sudo", 4100)));
+        let stat = "4300 (tmux: server) S 1 4300 4300 0 -1 4194560 101 0 0 0 0 0 0 0 20 0 1 0 123456";
+        assert_eq!(parse_name_and_parent(stat), Some(("tmux: server", 1)));
+        assert_eq!(parse_name_and_parent(""), None);
+    }
+}
@@ -0,0 +1,166 @@
+use std::ffi::OsString;
+use std::os::unix::ffi::OsStringExt;
+use std::os::unix::process::CommandExt;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::thread;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::logs::log;
+
+/// Everything needed to start a process again the way it was started:
+/// executable, arguments, working directory, environment and owner
+#[derive(Debug, Clone)]
+pub struct ProcessSnapshot {
+    pub exe: PathBuf,
+    pub argv: Vec<OsString>,
+    pub cwd: PathBuf,
+    pub env: Vec<(OsString, OsString)>,
+    pub owner: Credentials,
+}
+
+/// Real user, group and supplementary groups of a process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Credentials {
+    pub uid: libc::uid_t,
+    pub gid: libc::gid_t,
+    pub groups: Vec<libc::gid_t>,
+}
+
+impl ProcessSnapshot {
+    pub fn capture(pid: &str) -> Result<Self> {
+        let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).context("failed to read status")?;
+        let owner = parse_credentials(&status)?;
+        let euid = unsafe { libc::geteuid() };
+        if owner.uid != euid && euid != 0 {
+            return Err(anyhow!(
+                "it belongs to user {}, only its owner or Superuser can restart it",
+                owner.uid
+            ));
+        }
+        // argv[0] may have been rewritten by the process, e.g. with setproctitle
+        let exe = std::fs::read_link(format!("/proc/{}/exe", pid)).context("failed to read executable")?;
+        let exe = PathBuf::from(exe.to_string_lossy().trim_end_matches(" (deleted)")); // replaced by an upgrade
+        let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).context("failed to read command line")?;
+        let argv = split_nul(cmdline);
+        if argv.is_empty() {
+            return Err(anyhow!(
+                "it has no command line, e.g. it is a kernel thread or a zombie"
+            ));
+        }
+        let cwd = std::fs::read_link(format!("/proc/{}/cwd", pid)).context("failed to read working directory")?;
+        let environ = std::fs::read(format!("/proc/{}/environ", pid)).context("failed to read environment")?;
+        let env = split_nul(environ)
+            .into_iter()
+            .filter_map(|entry| {
+                let entry = entry.into_vec();
+                let separator = entry.iter().position(|b| *b == b'=')?;
+                let value = entry[separator + 1..].to_vec();
+                let mut key = entry;
+                key.truncate(separator);
+                Some((OsString::from_vec(key), OsString::from_vec(value)))
+            })
+            .collect();
+        Ok(ProcessSnapshot {
+            exe,
+            argv,
+            cwd,
+            env,
+            owner,
+        })
+    }
+
+    pub fn command_line(&self) -> String {
+        self.argv
+            .iter()
+            .map(|it| it.to_string_lossy().to_string())
+            .collect::<Vec<String>>()
+            .join(" ")
+    }
+
+    /// Start the process in a new session, detached from the terminal, and return its PID.
+    /// When run as Superuser, it drops to the credentials of the original owner.
+    pub fn relaunch(&self) -> Result<u32> {
+        log(format!(
+            "Relaunching {:?} ({}) in {} as user {}",
+            self.command_line(),
+            self.exe.display(),
+            self.cwd.display(),
+            self.owner.uid
+        )
+        .as_str());
+        let owner = match self.owner.uid == unsafe { libc::geteuid() } {
+            true => None,
+            false => Some(self.owner.clone()),
+        };
+        let mut command = Command::new(&self.exe);
+        command
+            .arg0(&self.argv[0])
+            .args(&self.argv[1..])
+            .current_dir(&self.cwd)
+            .env_clear()
+            .envs(self.env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null());
+        unsafe {
+            command.pre_exec(move || {
+                libc::setsid();
+                if let Some(owner) = &owner {
+                    // supplementary groups and group first, while still privileged to change them
+                    if libc::setgroups(owner.groups.len(), owner.groups.as_ptr()) != 0
+                        || libc::setgid(owner.gid) != 0
+                        || libc::setuid(owner.uid) != 0
+                    {
+                        return Err(std::io::Error::last_os_error());
+                    }
+                }
+                Ok(())
+            });
+        }
+        let mut child = command
+            .spawn()
+            .context(format!("failed to start {:?}", self.command_line()))?;
+        let pid = child.id();
+        thread::spawn(move || child.wait()); // reap it once it exits, so it doesn't linger as a zombie
+        Ok(pid)
+    }
+}
+
+/// Real IDs from the `Uid:`, `Gid:` and `Groups:` lines of `/proc/PID/status`
+fn parse_credentials(status: &str) -> Result<Credentials> {
+    let field = |key: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(key)?.strip_prefix(':'))
+            .map(|value| {
+                value
+                    .split_whitespace()
+                    .filter_map(|it| it.parse::<u32>().ok())
+                    .collect::<Vec<u32>>()
+            })
+    };
+    let real_id = |key: &str| {
+        field(key)
+            .and_then(|ids| ids.first().copied())
+            .ok_or(anyhow!("failed to read {} of the process", key))
+    };
+    Ok(Credentials {
+        uid: real_id("Uid")?,
+        gid: real_id("Gid")?,
+        groups: field("Groups").unwrap_or_default(),
+    })
+}
+
+/// Split NUL-terminated entries, keeping empty arguments
+fn split_nul(content: Vec<u8>) -> Vec<OsString> {
+    let content = content.strip_suffix(&[0]).unwrap_or(&content);
+    if content.is_empty() {
+        return vec![];
+    }
+    content
+        .split(|b| *b == 0)
+        .map(|it| OsString::from_vec(it.to_vec()))
+        .collect()
+}
@@ -0,0 +1,296 @@
+use std::io;
+
+use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
+
+use crate::escalation::execute_privileged;
+use crate::logs::log;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SchedPolicy {
+    #[default]
+    Other,
+    Fifo,
+    RoundRobin,
+    Batch,
+    Idle,
+    Deadline,
+}
+
+impl SchedPolicy {
+    /// Policy number as in `/proc/PID/stat`
+    pub fn from_raw(policy: u32) -> SchedPolicy {
+        match policy as i32 {
+            libc::SCHED_FIFO => SchedPolicy::Fifo,
+            libc::SCHED_RR => SchedPolicy::RoundRobin,
+            libc::SCHED_BATCH => SchedPolicy::Batch,
+            libc::SCHED_IDLE => SchedPolicy::Idle,
+            6 => SchedPolicy::Deadline, // SCHED_DEADLINE
+            _ => SchedPolicy::Other,
+        }
+    }
+
+    fn to_raw(self) -> i32 {
+        match self {
+            SchedPolicy::Other => libc::SCHED_OTHER,
+            SchedPolicy::Fifo => libc::SCHED_FIFO,
+            SchedPolicy::RoundRobin => libc::SCHED_RR,
+            SchedPolicy::Batch => libc::SCHED_BATCH,
+            SchedPolicy::Idle => libc::SCHED_IDLE,
+            SchedPolicy::Deadline => 6,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SchedPolicy::Other => "SCHED_OTHER",
+            SchedPolicy::Fifo => "SCHED_FIFO",
+            SchedPolicy::RoundRobin => "SCHED_RR",
+            SchedPolicy::Batch => "SCHED_BATCH",
+            SchedPolicy::Idle => "SCHED_IDLE",
+            SchedPolicy::Deadline => "SCHED_DEADLINE",
+        }
+    }
+
+    pub fn is_realtime(&self) -> bool {
+        matches!(self, SchedPolicy::Fifo | SchedPolicy::RoundRobin)
+    }
+
+    fn chrt_flag(&self) -> &'static str {
+        match self {
+            SchedPolicy::Other => "--other",
+            SchedPolicy::Fifo => "--fifo",
+            SchedPolicy::RoundRobin => "--rr",
+            SchedPolicy::Batch => "--batch",
+            SchedPolicy::Idle => "--idle",
+            SchedPolicy::Deadline => "--deadline",
+        }
+    }
+}
+
+const IOPRIO_WHO_PROCESS: i32 = 1;
+const IOPRIO_CLASS_SHIFT: i32 = 13;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum IoClass {
+    #[default]
+    None, // derived from the nice value
+    Realtime,
+    BestEffort,
+    Idle,
+}
+
+impl IoClass {
+    fn from_raw(class: i32) -> IoClass {
+        match class {
+            1 => IoClass::Realtime,
+            2 => IoClass::BestEffort,
+            3 => IoClass::Idle,
+            _ => IoClass::None,
+        }
+    }
+
+    fn to_raw(self) -> i32 {
+        match self {
+            IoClass::None => 0,
+            IoClass::Realtime => 1,
+            IoClass::BestEffort => 2,
+            IoClass::Idle => 3,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IoClass::None => "none",
+            IoClass::Realtime => "realtime",
+            IoClass::BestEffort => "best-effort",
+            IoClass::Idle => "idle",
+        }
+    }
+}
+
+/// I/O scheduling class and level (0 is the highest priority, 7 the lowest)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct IoPriority {
+    pub class: IoClass,
+    pub level: i32,
+}
+
+impl IoPriority {
+    pub fn describe(&self, nice: i32) -> String {
+        match self.class {
+            IoClass::None => format!("none, best-effort {} derived from nice", (nice + 20) / 5),
+            IoClass::Idle => "idle".to_string(),
+            _ => format!("{} {}", self.class.name(), self.level),
+        }
+    }
+}
+
+pub fn get_io_priority(pid: &str) -> Result<IoPriority> {
+    let pid_num: libc::pid_t = pid.parse().context("invalid PID")?;
+    let ioprio = unsafe { libc::syscall(libc::SYS_ioprio_get, IOPRIO_WHO_PROCESS, pid_num) } as i32;
+    if ioprio < 0 {
+        return Err(errno_error(io::Error::last_os_error(), pid));
+    }
+    Ok(IoPriority {
+        class: IoClass::from_raw(ioprio >> IOPRIO_CLASS_SHIFT),
+        level: ioprio & ((1 << IOPRIO_CLASS_SHIFT) - 1),
+    })
+}
+
+/// Set I/O priority of all threads of a process, falling back to privileged `ionice` when not permitted
+pub fn set_io_priority(pid: &str, priority: IoPriority) -> Result<()> {
+    let tids = process_tasks(pid)?;
+    log(format!("Setting I/O priority {:?} of PID {}", priority, pid).as_str());
+    let ioprio = (priority.class.to_raw() << IOPRIO_CLASS_SHIFT) | priority.level;
+    let result = for_each_task(pid, &tids, |tid| unsafe {
+        libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, ioprio) as i32
+    });
+    match result {
+        Err(error) if is_permission_error(&error) => execute_privileged(ionice_command(priority, &tids)),
+        Err(error) => Err(errno_error(error, pid)),
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Set nice value of all threads of a process, falling back to privileged `renice` when not permitted
+pub fn set_nice(pid: &str, nice: i32) -> Result<()> {
+    let tids = process_tasks(pid)?;
+    log(format!("Setting nice value {} of PID {}", nice, pid).as_str());
+    let result = for_each_task(pid, &tids, |tid| unsafe {
+        libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, nice)
+    });
+    match result {
+        Err(error) if is_permission_error(&error) => execute_privileged(renice_command(nice, &tids)),
+        Err(error) => Err(errno_error(error, pid)),
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Set scheduling policy of all threads of a process, falling back to privileged `chrt` when not permitted
+pub fn set_scheduler(pid: &str, policy: SchedPolicy, priority: i32) -> Result<()> {
+    let tids = process_tasks(pid)?;
+    log(format!(
+        "Setting scheduling policy {} {} of PID {}",
+        policy.name(),
+        priority,
+        pid
+    )
+    .as_str());
+    let param = libc::sched_param {
+        sched_priority: priority,
+    };
+    let result = for_each_task(pid, &tids, |tid| unsafe {
+        libc::sched_setscheduler(tid, policy.to_raw(), &param)
+    });
+    match result {
+        Err(error) if is_permission_error(&error) => execute_privileged(chrt_command(policy, priority, pid)),
+        Err(error) => Err(errno_error(error, pid)),
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Cores the process is allowed to run on, indexed by core number
+pub fn get_affinity(pid: &str, cpu_num: usize) -> Result<Vec<bool>> {
+    let pid_num: libc::pid_t = pid.parse().context("invalid PID")?;
+    let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    let result = unsafe { libc::sched_getaffinity(pid_num, std::mem::size_of::<libc::cpu_set_t>(), &mut cpu_set) };
+    if result != 0 {
+        return Err(errno_error(io::Error::last_os_error(), pid));
+    }
+    Ok((0..cpu_num)
+        .map(|core| unsafe { libc::CPU_ISSET(core, &cpu_set) })
+        .collect())
+}
+
+/// Restrict a process (and optionally all its threads) to the given cores, falling back to privileged `taskset`
+pub fn set_affinity(pid: &str, cores: &[usize], all_threads: bool) -> Result<()> {
+    let tids = match all_threads {
+        true => process_tasks(pid)?,
+        false => vec![pid.to_string()],
+    };
+    let cores_list = cores.iter().map(|core| core.to_string()).join(",");
+    log(format!("Setting CPU affinity {} of PID {}", cores_list, pid).as_str());
+    let mut cpu_set: libc::cpu_set_t = unsafe { std::mem::zeroed() };
+    for core in cores {
+        unsafe { libc::CPU_SET(*core, &mut cpu_set) };
+    }
+    let result = for_each_task(pid, &tids, |tid| unsafe {
+        libc::sched_setaffinity(tid, std::mem::size_of::<libc::cpu_set_t>(), &cpu_set)
+    });
+    match result {
+        Err(error) if is_permission_error(&error) => execute_privileged(taskset_command(cores, all_threads, pid)),
+        Err(error) => Err(errno_error(error, pid)),
+        Ok(()) => Ok(()),
+    }
+}
+
+/// Privileged fallback of `set_io_priority`
+pub fn ionice_command(priority: IoPriority, tids: &[String]) -> String {
+    format!(
+        "ionice -c {} -n {} -p {}",
+        priority.class.to_raw(),
+        priority.level,
+        tids.join(" ")
+    )
+}
+
+/// Privileged fallback of `set_nice`
+pub fn renice_command(nice: i32, tids: &[String]) -> String {
+    format!("renice -n {} -p {}", nice, tids.join(" "))
+}
+
+/// Privileged fallback of `set_scheduler`
+pub fn chrt_command(policy: SchedPolicy, priority: i32, pid: &str) -> String {
+    format!("chrt --all-tasks {} -p {} {}", policy.chrt_flag(), priority, pid)
+}
+
+/// Privileged fallback of `set_affinity`
+pub fn taskset_command(cores: &[usize], all_threads: bool, pid: &str) -> String {
+    let all_tasks_flag = if all_threads { " --all-tasks" } else { "" };
+    let cores_list = cores.iter().map(|core| core.to_string()).join(",");
+    format!("taskset{} -p -c {} {}", all_tasks_flag, cores_list, pid)
+}
+
+/// Thread IDs of a process, scheduling attributes are set per thread on Linux
+pub fn process_tasks(pid: &str) -> Result<Vec<String>> {
+    let entries =
+        std::fs::read_dir(format!("/proc/{}/task", pid)).context(format!("process {} no longer exists", pid))?;
+    let tids: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect();
+    Ok(tids)
+}
+
+/// Apply a syscall to each thread, ignoring threads that exited in the meantime
+pub fn for_each_task(pid: &str, tids: &[String], syscall: impl Fn(libc::pid_t) -> i32) -> Result<(), io::Error> {
+    for tid in tids {
+        let tid_num: libc::pid_t = match tid.parse() {
+            Ok(num) => num,
+            Err(_) => continue,
+        };
+        if syscall(tid_num) == 0 {
+            continue;
+        }
+        let error = io::Error::last_os_error();
+        if error.raw_os_error() == Some(libc::ESRCH) && tid != pid {
+            continue;
+        }
+        return Err(error);
+    }
+    Ok(())
+}
+
+pub fn is_permission_error(error: &io::Error) -> bool {
+    matches!(error.raw_os_error(), Some(libc::EPERM) | Some(libc::EACCES))
+}
+
+pub fn errno_error(error: io::Error, pid: &str) -> anyhow::Error {
+    log(format!("Failed to change process {}: {}", pid, error).as_str());
+    match error.raw_os_error() {
+        Some(libc::ESRCH) => anyhow!("process {} no longer exists", pid),
+        Some(libc::EINVAL) => anyhow!("invalid value: {}", error),
+        _ => anyhow!(error),
+    }
+}
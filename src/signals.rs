@@ -0,0 +1,234 @@
+use std::fmt;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+use crate::kill_sequence::is_process_alive;
+use crate::logs::log;
+use crate::sysinfo::read_process_start_time;
+
+#[derive(Debug)]
+pub enum KillError {
+    NoSuchProcess,                   // ESRCH: process has already exited
+    PermissionDenied,                // EPERM: process belongs to another user
+    InvalidSignal,                   // EINVAL: signal not supported
+    InvalidPid(String),              // PID that must not be passed to kill(2)
+    ProcessReplaced,                 // process exited and its PID was reused by another one
+    Os(io::Error),                   // any other errno
+    AuthenticationRequired,          // escalation backend needs a password
+    EscalationFailed(anyhow::Error), // privileged subprocess failed
+}
+
+impl fmt::Display for KillError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KillError::NoSuchProcess => write!(f, "no such process"),
+            KillError::PermissionDenied => write!(f, "operation not permitted"),
+            KillError::InvalidSignal => write!(f, "invalid signal"),
+            KillError::InvalidPid(pid) => write!(f, "invalid process ID: {:?}", pid),
+            KillError::ProcessReplaced => write!(f, "process has exited and its PID was reused by another process"),
+            KillError::Os(e) => write!(f, "{}", e),
+            KillError::AuthenticationRequired => write!(f, "authentication required"),
+            KillError::EscalationFailed(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for KillError {}
+
+/// Process pinned by its PID and start time, so that signals can't hit another process that reused the PID.
+/// Holds a pidfd on kernels supporting it (5.3+), otherwise the start time is checked right before signalling.
+#[derive(Debug)]
+pub struct ProcessHandle {
+    pub pid: String,
+    pid_num: libc::pid_t,
+    start_time: Option<u64>, // clock ticks after boot, as seen when the process list was refreshed
+    pidfd: Option<OwnedFd>,
+}
+
+impl ProcessHandle {
+    /// Fails if the process is gone or the PID already belongs to a process started at a different time
+    pub fn open(pid: &str, start_time: Option<u64>) -> Result<Self, KillError> {
+        let pid_num: libc::pid_t = match pid.parse::<libc::pid_t>() {
+            Ok(num) if num > 0 => num,
+            _ => return Err(KillError::InvalidPid(pid.to_string())), // 0 and negative values target process groups
+        };
+        let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid_num, 0) };
+        let pidfd = match fd {
+            fd if fd >= 0 => Some(unsafe { OwnedFd::from_raw_fd(fd as i32) }),
+            _ => match io::Error::last_os_error().raw_os_error() {
+                Some(libc::ESRCH) => return Err(KillError::NoSuchProcess),
+                _ => None, // not supported by the kernel, fall back to kill(2)
+            },
+        };
+        let handle = ProcessHandle {
+            pid: pid.to_string(),
+            pid_num,
+            start_time,
+            pidfd,
+        };
+        handle.verify_identity()?;
+        Ok(handle)
+    }
+
+    /// Check that the PID still refers to the same process. With a pidfd it's enough to do it once after opening it.
+    fn verify_identity(&self) -> Result<(), KillError> {
+        let actual = read_process_start_time(&self.pid).map_err(|_| KillError::NoSuchProcess)?;
+        match self.start_time {
+            Some(expected) if expected != actual => {
+                log(format!(
+                    "PID {} was reused: started at {} instead of {}",
+                    self.pid, actual, expected
+                )
+                .as_str());
+                Err(KillError::ProcessReplaced)
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Deliver a signal without spawning any subprocess, with pidfd_send_signal(2) or kill(2)
+    pub fn send_signal(&self, signal: i32) -> Result<(), KillError> {
+        log(format!("Sending {} to PID {}", signal_name(signal), self.pid).as_str());
+        let result = match &self.pidfd {
+            Some(pidfd) => unsafe {
+                libc::syscall(
+                    libc::SYS_pidfd_send_signal,
+                    pidfd.as_raw_fd(),
+                    signal,
+                    std::ptr::null::<libc::siginfo_t>(),
+                    0,
+                ) as i32
+            },
+            None => {
+                self.verify_identity()?;
+                unsafe { libc::kill(self.pid_num, signal) }
+            }
+        };
+        if result == 0 {
+            return Ok(());
+        }
+        let error = io::Error::last_os_error();
+        log(format!("Failed to send {} to PID {}: {}", signal_name(signal), self.pid, error).as_str());
+        match error.raw_os_error() {
+            Some(libc::ESRCH) => Err(KillError::NoSuchProcess),
+            Some(libc::EPERM) => Err(KillError::PermissionDenied),
+            Some(libc::EINVAL) => Err(KillError::InvalidSignal),
+            _ => Err(KillError::Os(error)),
+        }
+    }
+
+    /// Process is alive until it exits, zombies waiting to be reaped count as dead
+    pub fn is_alive(&self) -> bool {
+        match &self.pidfd {
+            Some(pidfd) => {
+                let mut poll_fd = libc::pollfd {
+                    fd: pidfd.as_raw_fd(),
+                    events: libc::POLLIN, // pidfd becomes readable when the process exits
+                    revents: 0,
+                };
+                unsafe { libc::poll(&mut poll_fd, 1, 0) == 0 }
+            }
+            None => is_process_alive(&self.pid) && self.verify_identity().is_ok(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SignalInfo {
+    pub number: i32,
+    pub name: String,
+    pub description: &'static str,
+}
+
+impl SignalInfo {
+    fn new(number: i32, name: &str, description: &'static str) -> Self {
+        SignalInfo {
+            number,
+            name: name.to_string(),
+            description,
+        }
+    }
+
+    pub fn menu_label(&self) -> String {
+        format!("{:>2} {:<12} {}", self.number, self.name, self.description)
+    }
+}
+
+/// All standard and real-time signals supported by Linux, ordered by number
+pub fn linux_signals() -> Vec<SignalInfo> {
+    let mut signals = vec![
+        SignalInfo::new(libc::SIGHUP, "SIGHUP", "Hangup, daemons reload their configuration"),
+        SignalInfo::new(libc::SIGINT, "SIGINT", "Interrupt from keyboard (Ctrl+C)"),
+        SignalInfo::new(libc::SIGQUIT, "SIGQUIT", "Quit and dump core, JVM prints a thread dump"),
+        SignalInfo::new(libc::SIGILL, "SIGILL", "Illegal instruction"),
+        SignalInfo::new(libc::SIGTRAP, "SIGTRAP", "Trace or breakpoint trap"),
+        SignalInfo::new(libc::SIGABRT, "SIGABRT", "Abort and dump core"),
+        SignalInfo::new(libc::SIGBUS, "SIGBUS", "Bus error, bad memory access"),
+        SignalInfo::new(libc::SIGFPE, "SIGFPE", "Floating-point exception"),
+        SignalInfo::new(libc::SIGKILL, "SIGKILL", "Kill immediately, cannot be caught"),
+        SignalInfo::new(libc::SIGUSR1, "SIGUSR1", "User-defined signal 1, e.g. reopen logs"),
+        SignalInfo::new(libc::SIGSEGV, "SIGSEGV", "Invalid memory reference"),
+        SignalInfo::new(libc::SIGUSR2, "SIGUSR2", "User-defined signal 2"),
+        SignalInfo::new(libc::SIGPIPE, "SIGPIPE", "Broken pipe, write with no readers"),
+        SignalInfo::new(libc::SIGALRM, "SIGALRM", "Timer signal from alarm"),
+        SignalInfo::new(libc::SIGTERM, "SIGTERM", "Terminate gracefully"),
+        SignalInfo::new(libc::SIGSTKFLT, "SIGSTKFLT", "Stack fault on coprocessor, unused"),
+        SignalInfo::new(libc::SIGCHLD, "SIGCHLD", "Child stopped or terminated"),
+        SignalInfo::new(libc::SIGCONT, "SIGCONT", "Continue if stopped"),
+        SignalInfo::new(libc::SIGSTOP, "SIGSTOP", "Stop process, cannot be caught"),
+        SignalInfo::new(libc::SIGTSTP, "SIGTSTP", "Stop typed at terminal (Ctrl+Z)"),
+        SignalInfo::new(libc::SIGTTIN, "SIGTTIN", "Terminal input for background process"),
+        SignalInfo::new(libc::SIGTTOU, "SIGTTOU", "Terminal output for background process"),
+        SignalInfo::new(libc::SIGURG, "SIGURG", "Urgent condition on socket"),
+        SignalInfo::new(libc::SIGXCPU, "SIGXCPU", "CPU time limit exceeded"),
+        SignalInfo::new(libc::SIGXFSZ, "SIGXFSZ", "File size limit exceeded"),
+        SignalInfo::new(libc::SIGVTALRM, "SIGVTALRM", "Virtual alarm clock"),
+        SignalInfo::new(libc::SIGPROF, "SIGPROF", "Profiling timer expired"),
+        SignalInfo::new(libc::SIGWINCH, "SIGWINCH", "Terminal window resized"),
+        SignalInfo::new(libc::SIGIO, "SIGIO", "I/O now possible"),
+        SignalInfo::new(libc::SIGPWR, "SIGPWR", "Power failure"),
+        SignalInfo::new(libc::SIGSYS, "SIGSYS", "Bad system call"),
+    ];
+    signals.sort_by_key(|it| it.number);
+
+    let rt_min = libc::SIGRTMIN();
+    let rt_max = libc::SIGRTMAX();
+    let rt_middle = (rt_min + rt_max) / 2;
+    for number in rt_min..=rt_max {
+        let name = match number {
+            _ if number == rt_min => "SIGRTMIN".to_string(),
+            _ if number == rt_max => "SIGRTMAX".to_string(),
+            _ if number <= rt_middle => format!("SIGRTMIN+{}", number - rt_min),
+            _ => format!("SIGRTMAX-{}", rt_max - number),
+        };
+        signals.push(SignalInfo {
+            number,
+            name,
+            description: "Real-time signal, application-defined",
+        });
+    }
+    signals
+}
+
+/// Whether the default action of the signal ends the process, unless the process handles it
+pub fn ends_process(signal: i32) -> bool {
+    !matches!(
+        signal,
+        libc::SIGSTOP
+            | libc::SIGTSTP
+            | libc::SIGTTIN
+            | libc::SIGTTOU
+            | libc::SIGCONT
+            | libc::SIGCHLD
+            | libc::SIGURG
+            | libc::SIGWINCH
+    )
+}
+
+pub fn signal_name(signal: i32) -> String {
+    linux_signals()
+        .into_iter()
+        .find(|it| it.number == signal)
+        .map(|it| it.name)
+        .unwrap_or_else(|| format!("signal {}", signal))
+}
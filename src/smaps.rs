@@ -0,0 +1,117 @@
+use anyhow::{anyhow, Context, Result};
+
+use crate::numbers::format_bytes;
+
+/// Memory of a process split by how its pages are shared, in bytes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemoryBreakdown {
+    pub rss: u64,      // resident pages, shared ones counted in full by every process using them
+    pub pss: u64,      // private pages plus an equal share of the shared ones, sums up correctly across processes
+    pub uss: u64,      // private pages only, the memory freed by killing the process
+    pub rss_anon: u64, // resident anonymous memory, e.g. heap and stack
+    pub rss_file: u64, // resident pages of mapped files, e.g. libraries
+    pub shmem: u64,    // resident shared memory, e.g. tmpfs files and System V segments
+    pub swap: u64,
+}
+
+impl MemoryBreakdown {
+    pub fn describe(&self) -> String {
+        format!(
+            "PSS {}, USS {}, RSS {} (anonymous {}, file-backed {}, shmem {}), swap {}",
+            format_bytes(self.pss),
+            format_bytes(self.uss),
+            format_bytes(self.rss),
+            format_bytes(self.rss_anon),
+            format_bytes(self.rss_file),
+            format_bytes(self.shmem),
+            format_bytes(self.swap),
+        )
+    }
+
+    /// Total of the group members whose memory could be read, None if none could
+    pub fn sum<'a>(breakdowns: impl Iterator<Item = &'a MemoryBreakdown>) -> Option<MemoryBreakdown> {
+        breakdowns.fold(None, |total, it| {
+            let total = total.unwrap_or_default();
+            Some(MemoryBreakdown {
+                rss: total.rss + it.rss,
+                pss: total.pss + it.pss,
+                uss: total.uss + it.uss,
+                rss_anon: total.rss_anon + it.rss_anon,
+                rss_file: total.rss_file + it.rss_file,
+                shmem: total.shmem + it.shmem,
+                swap: total.swap + it.swap,
+            })
+        })
+    }
+}
+
+/// Read the totals of `/proc/PID/smaps_rollup`, and the resident memory split of `/proc/PID/status`.
+/// Fails for other users' processes unless running as Superuser.
+pub fn read_memory_breakdown(pid: &str) -> Result<MemoryBreakdown> {
+    let rollup = std::fs::read_to_string(format!("/proc/{}/smaps_rollup", pid)).context("reading smaps_rollup")?;
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).context("reading status")?;
+    let rss = read_kb_field(&rollup, "Rss").ok_or(anyhow!("no memory mappings, e.g. a kernel thread"))?;
+    let rollup_kb = |key: &str| read_kb_field(&rollup, key).unwrap_or(0);
+    let status_kb = |key: &str| read_kb_field(&status, key).unwrap_or(0);
+    Ok(MemoryBreakdown {
+        rss: rss * 1024,
+        pss: rollup_kb("Pss") * 1024,
+        uss: (rollup_kb("Private_Clean") + rollup_kb("Private_Dirty")) * 1024,
+        rss_anon: status_kb("RssAnon") * 1024,
+        rss_file: status_kb("RssFile") * 1024,
+        shmem: status_kb("RssShmem") * 1024,
+        swap: rollup_kb("Swap") * 1024,
+    })
+}
+
+/// Value of a line like `Pss:    478 kB`
+fn read_kb_field(content: &str, key: &str) -> Option<u64> {
+    content.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        match name == key {
+            true => value.split_whitespace().next()?.parse::<u64>().ok(),
+            false => None,
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ROLLUP: &str = "55d0c8a4e000-7ffd2a1f6000 ---p 00000000 00:00 0                          [rollup]
+Rss:                4321 kB
+Pss:                 478 kB
+Pss_Anon:            120 kB
+Private_Clean:        64 kB
+Private_Dirty:       256 kB
+Swap:                  0 kB
+";
+
+    #[test]
+    fn test_read_kb_field() {
+        assert_eq!(read_kb_field(ROLLUP, "Rss"), Some(4321));
+        assert_eq!(read_kb_field(ROLLUP, "Pss"), Some(478));
+        assert_eq!(read_kb_field(ROLLUP, "Private_Dirty"), Some(256));
+        assert_eq!(read_kb_field(ROLLUP, "Swap"), Some(0));
+        assert_eq!(read_kb_field(ROLLUP, "SwapPss"), None);
+        assert_eq!(
+            read_kb_field("Name:\tbash\nRssAnon:\t    1024 kB\n", "RssAnon"),
+            Some(1024)
+        );
+        assert_eq!(read_kb_field("Rss: unknown kB\n", "Rss"), None);
+    }
+
+    #[test]
+    fn test_sum() {
+        assert!(MemoryBreakdown::sum([].iter()).is_none());
+        let process = MemoryBreakdown {
+            rss: 10,
+            pss: 5,
+            uss: 2,
+            ..Default::default()
+        };
+        let total = MemoryBreakdown::sum([process, process].iter()).unwrap();
+        assert_eq!((total.rss, total.pss, total.uss, total.swap), (20, 10, 4, 0));
+    }
+}
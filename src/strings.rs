@@ -23,3 +23,16 @@ pub fn first_cmd_part(cmd: &str) -> String {
     }
     parts[0].to_string()
 }
+
+/// Shorten the text to at most `max` characters, ending it with an ellipsis if it was cut
+pub fn truncate_ellipsis(text: &str, max: usize) -> String {
+    match text.chars().count() > max {
+        true => text.chars().take(max.saturating_sub(1)).chain(['…']).collect(),
+        false => text.to_string(),
+    }
+}
+
+/// Quote a value to be passed as a single shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
@@ -2,13 +2,19 @@ use std::time::SystemTime;
 use std::{collections::HashMap, ops::Deref};
 
 use anyhow::{anyhow, Context, Result};
+use itertools::Itertools;
 use libc::{sysconf, _SC_CLK_TCK};
-use sysinfo::{ComponentExt, DiskExt, NetworkExt, Process, ProcessExt, System, SystemExt, Uid};
+use sysinfo::{ComponentExt, DiskExt, NetworkExt, Process, ProcessExt, System, SystemExt, Uid, UserExt};
 
 use crate::logs::log;
 use crate::numbers::PercentFormatterExt;
-use crate::numbers::{format_duration, ClampNumExt};
+use crate::numbers::{format_bytes, format_duration, ClampNumExt};
+use crate::oom::read_oom_score;
+use crate::protection::Protection;
+use crate::scheduling::{get_io_priority, IoPriority, SchedPolicy};
+use crate::smaps::{read_memory_breakdown, MemoryBreakdown};
 use crate::strings::first_cmd_part;
+use crate::systemd::{read_systemd_unit, SystemdUnit};
 
 #[derive(Debug, Default, Clone)]
 pub struct SystemProcStats {
@@ -25,15 +31,34 @@ pub struct ProcessStat {
     pub exe: String,  // Full executable path, e.g. /opt/google/chrome/chrome
     pub cwd: String,
     pub status: String,
-    pub cpu_usage: f64,    // fraction of 1 core, [0-CORES]
-    pub memory_usage: f64, // fraction of total memory
-    pub disk_usage: f64,   // Total written + read bytes
+    pub cpu_usage: f64,                  // fraction of 1 core, [0-CORES]
+    pub memory_usage: f64,               // fraction of total memory, by RSS for processes and by PSS for groups
+    pub pss_usage: f64,                  // fraction of total memory by PSS, same as memory_usage when PSS is unknown
+    pub memory: Option<MemoryBreakdown>, // None when not read or it can't be, e.g. for other users' processes
+    pub disk_usage: f64,                 // Total written + read bytes
+    pub disk_read_bytes: u64,            // total read from storage
+    pub disk_written_bytes: u64,         // total written to storage
+    pub disk_read_rate: f64,             // bytes per second since the previous refresh
+    pub disk_write_rate: f64,            // bytes per second since the previous refresh
     pub user_id: Option<u32>,
+    pub user_name: String,
     pub display_name: String,
     pub run_time: u64, // uptime in seconds
     pub time_ms: u64,  // timestamp of reading statistics
     pub cpu_time: f64, // in seconds
     pub parent_pid: Option<String>,
+    pub nice: i32, // -20 (highest priority) to 19 (lowest)
+    pub sched_policy: SchedPolicy,
+    pub rt_priority: u32, // 1-99 for real-time policies, 0 otherwise
+    pub io_priority: IoPriority,
+    pub oom_score: i32,     // 0-2000, the higher the sooner the OOM killer picks it
+    pub oom_score_adj: i32, // -1000 (never kill) to 1000 (kill first)
+    pub start_time: u64,    // clock ticks after boot, identifies the process together with PID
+    pub session_id: u32,
+    pub kernel_thread: bool,
+    pub protection: Option<Protection>, // guarded against disruptive signals
+    pub systemd_unit: Option<SystemdUnit>,
+    pub holds_path: Vec<String>, // how it keeps the filtered path busy, e.g. "cwd", "fd 3"
     pub group_children: Vec<ProcessStat>,
 }
 
@@ -42,6 +67,22 @@ impl ProcessStat {
         format!("{} {}", self.pid, self.display_name)
     }
 
+    /// PIDs targeted by actions: all members of a group or the process itself
+    pub fn member_pids(&self) -> Vec<String> {
+        match self.group_children.is_empty() {
+            true => vec![self.pid.clone()],
+            false => self.group_children.iter().map(|p| p.pid.clone()).collect(),
+        }
+    }
+
+    /// Whether the process (or every member of the group) is stopped by a signal
+    pub fn is_stopped(&self) -> bool {
+        match self.group_children.is_empty() {
+            true => self.status == "Stopped",
+            false => self.group_children.iter().all(|p| p.is_stopped()),
+        }
+    }
+
     pub fn calculate_cpu_usage(&self, previous_processes: &Vec<ProcessStat>) -> f64 {
         let previous_proc: Option<&ProcessStat> = previous_processes.iter().find(|p| p.pid == self.pid);
         if previous_proc.is_none() {
@@ -55,6 +96,61 @@ impl ProcessStat {
         delta_cpu_ms / delta_time_ms as f64
     }
 
+    /// Bytes read and written per second since the previous reading of the same process
+    pub fn calculate_disk_io_rates(&self, previous_processes: &[ProcessStat]) -> (f64, f64) {
+        let Some(previous_proc) = previous_processes.iter().find(|p| p.pid == self.pid) else {
+            return (0f64, 0f64);
+        };
+        let delta_time_ms = self.time_ms.saturating_sub(previous_proc.time_ms);
+        if delta_time_ms == 0 {
+            return (0f64, 0f64);
+        }
+        let rate =
+            |current: u64, previous: u64| current.saturating_sub(previous) as f64 * 1000f64 / delta_time_ms as f64;
+        (
+            rate(self.disk_read_bytes, previous_proc.disk_read_bytes),
+            rate(self.disk_written_bytes, previous_proc.disk_written_bytes),
+        )
+    }
+
+    /// Read and write rate together
+    pub fn disk_io_rate(&self) -> f64 {
+        self.disk_read_rate + self.disk_write_rate
+    }
+
+    pub fn format_disk_io_rate(&self) -> String {
+        format!("{}/s", format_bytes(self.disk_io_rate() as u64))
+    }
+
+    fn describe_disk_io(&self) -> String {
+        format!(
+            "read {}/s, write {}/s (total read {}, written {})",
+            format_bytes(self.disk_read_rate as u64),
+            format_bytes(self.disk_write_rate as u64),
+            format_bytes(self.disk_read_bytes),
+            format_bytes(self.disk_written_bytes),
+        )
+    }
+
+    /// Scheduling class and priority in the style of `ps -o cls,ni`
+    pub fn format_priority(&self) -> String {
+        match self.sched_policy {
+            SchedPolicy::Other => format!("TS {}", self.nice),
+            SchedPolicy::Batch => format!("B {}", self.nice),
+            SchedPolicy::Idle => "IDL".to_string(),
+            SchedPolicy::Fifo => format!("FF {}", self.rt_priority),
+            SchedPolicy::RoundRobin => format!("RR {}", self.rt_priority),
+            SchedPolicy::Deadline => "DLN".to_string(),
+        }
+    }
+
+    fn describe_scheduling(&self) -> String {
+        match self.sched_policy.is_realtime() {
+            true => format!("{}, real-time priority {}", self.sched_policy.name(), self.rt_priority),
+            false => format!("{}, nice {}", self.sched_policy.name(), self.nice),
+        }
+    }
+
     pub fn format_cpu_usage(&self) -> String {
         if self.cpu_usage == 0f64 {
             return "0%".to_string();
@@ -83,11 +179,19 @@ impl ProcessStat {
         format!(
             "Process ID: {}
 Parent Process ID: {}
+User: {}
 User ID: {}
 Uptime: {}
 Status: {}
+Protected: {}
+Systemd unit: {}
+Scheduling: {}
+I/O priority: {}
+OOM score: {} (adjustment: {})
 Memory usage: {}
+Memory breakdown: {}
 CPU usage: {} / {}
+Disk I/O: {}
 
 Full command: {}
 
@@ -97,16 +201,45 @@ Working directory: {}
 ",
             self.pid,
             parent_pid_str,
+            self.user_name,
             user_id_str,
             uptime,
             self.status,
+            self.protection
+                .as_ref()
+                .map(|it| format!("yes, {}", it.describe()))
+                .unwrap_or("no".to_string()),
+            self.systemd_unit
+                .as_ref()
+                .map(|it| it.describe())
+                .unwrap_or("none".to_string()),
+            self.describe_scheduling(),
+            self.io_priority.describe(self.nice),
+            self.oom_score,
+            self.oom_score_adj,
             mem_usage,
+            self.describe_memory(),
             cpu_usage,
             max_cpu_usage,
+            self.describe_disk_io(),
             self.full_command(),
             self.exe,
             self.cwd,
-        )
+        ) + &self.describe_holds_path()
+    }
+
+    fn describe_memory(&self) -> String {
+        self.memory
+            .or_else(|| read_memory_breakdown(&self.pid).ok())
+            .map(|it| it.describe())
+            .unwrap_or("unavailable".to_string())
+    }
+
+    fn describe_holds_path(&self) -> String {
+        match self.holds_path.is_empty() {
+            true => String::new(),
+            false => format!("\nKeeps the filtered path busy: {}\n", self.holds_path.join(", ")),
+        }
     }
 
     pub fn group_details(&self, sys_stat: &SystemStat) -> String {
@@ -143,11 +276,14 @@ Working directory: {}
             "Processes in the group: {}
 Process IDs: {}
 Parent Process IDs: {}
+User: {}
 User ID: {}
 Uptime: {}
 Status: {}
-Memory usage: {}
+Memory usage: {} (PSS, shared memory counted once)
+Memory breakdown: {}
 CPU usage: {} / {}
+Disk I/O: {}
 
 Executable path: {}
 
@@ -157,15 +293,18 @@ Full commands:
             self.group_children.len(),
             pids,
             parent_pids,
+            self.user_name,
             user_id_str,
             uptime,
             statuses,
             mem_usage,
+            self.describe_memory(),
             cpu_usage,
             max_cpu_usage,
+            self.describe_disk_io(),
             self.exe,
             commands,
-        )
+        ) + &self.describe_holds_path()
     }
 }
 
@@ -239,7 +378,9 @@ pub struct CpuLoadAvg {
     pub load_15m: f64, // 0-100%
 }
 
-pub fn get_proc_stats(memstat: &SystemMemoryStat, sys: &mut System) -> SystemProcStats {
+/// Read statistics of all processes, with the memory breakdown only if requested,
+/// since smaps_rollup walks all memory mappings of every process
+pub fn get_proc_stats(memstat: &SystemMemoryStat, sys: &mut System, memory_breakdown: bool) -> SystemProcStats {
     sys.refresh_processes();
 
     let clk_tck: i64 = get_clock_ticks();
@@ -251,6 +392,11 @@ pub fn get_proc_stats(memstat: &SystemMemoryStat, sys: &mut System) -> SystemPro
         .as_millis() as u64;
     for (pid, process) in process_map {
         let user_id: Option<u32> = process.user_id().map(|uid: &Uid| *uid.deref());
+        let user_name: String = process
+            .user_id()
+            .and_then(|uid| sys.get_user_by_id(uid))
+            .map(|user| user.name().to_string())
+            .unwrap_or_default();
         let cmd = process.cmd().join(" ");
         let proc_name = process.name().to_string();
         let display_name: String = match cmd.is_empty() {
@@ -260,8 +406,20 @@ pub fn get_proc_stats(memstat: &SystemMemoryStat, sys: &mut System) -> SystemPro
         let exe_path: String = extract_exe_path(process);
         let cwd: String = process.cwd().to_string_lossy().to_string();
         let mem_usage_fraction: f64 = process.memory() as f64 / 1024f64 / memstat.total as f64;
-        let disk_usage = process.disk_usage().total_written_bytes as f64 + process.disk_usage().total_read_bytes as f64;
-        let cpu_time = read_process_cpu_time(pid.to_string()).unwrap_or(0) as f64 / clk_tck as f64;
+        let memory = match memory_breakdown {
+            true => read_memory_breakdown(&pid.to_string()).ok(),
+            false => None,
+        };
+        let pss_usage_fraction: f64 = match memory {
+            Some(memory) => memory.pss as f64 / 1024f64 / memstat.total as f64,
+            None => mem_usage_fraction,
+        };
+        let disk_read_bytes = process.disk_usage().total_read_bytes;
+        let disk_written_bytes = process.disk_usage().total_written_bytes;
+        let disk_usage = disk_written_bytes as f64 + disk_read_bytes as f64;
+        let proc_stat_fields = read_process_stat(&pid.to_string()).unwrap_or_default();
+        let (oom_score, oom_score_adj) = read_oom_score(&pid.to_string()).unwrap_or((0, 0));
+        let cpu_time = proc_stat_fields.cpu_ticks as f64 / clk_tck as f64;
         let cpu_usage = process.cpu_usage() as f64 / 100f64;
         let parent_pid = process.parent().map(|p| p.to_string());
         let status = process.status().to_string();
@@ -276,13 +434,32 @@ pub fn get_proc_stats(memstat: &SystemMemoryStat, sys: &mut System) -> SystemPro
             status,
             cpu_usage,
             memory_usage: mem_usage_fraction,
+            pss_usage: pss_usage_fraction,
+            memory,
             disk_usage,
+            disk_read_bytes,
+            disk_written_bytes,
+            disk_read_rate: 0f64,
+            disk_write_rate: 0f64,
             user_id,
+            user_name,
             display_name,
             run_time: process.run_time(),
             time_ms: timestamp_ms,
             cpu_time,
             parent_pid,
+            nice: proc_stat_fields.nice,
+            sched_policy: SchedPolicy::from_raw(proc_stat_fields.policy),
+            rt_priority: proc_stat_fields.rt_priority,
+            io_priority: get_io_priority(&pid.to_string()).unwrap_or_default(),
+            oom_score,
+            oom_score_adj,
+            start_time: proc_stat_fields.start_time,
+            session_id: proc_stat_fields.session_id,
+            kernel_thread: proc_stat_fields.flags & PF_KTHREAD != 0,
+            protection: None,
+            systemd_unit: read_systemd_unit(&pid.to_string()),
+            holds_path: Vec::new(),
             group_children: Vec::new(),
         };
         processes.push(process_stat);
@@ -488,15 +665,43 @@ fn read_system_cpu_stats(cpu_num: usize) -> Result<SystemCpuStat> {
     })
 }
 
-fn read_process_cpu_time(pid: String) -> Result<u64> {
+const PF_KTHREAD: u32 = 0x00200000; // process flag of kernel threads
+
+#[derive(Debug, Default, Clone)]
+struct ProcStatFields {
+    cpu_ticks: u64, // utime + stime
+    nice: i32,
+    rt_priority: u32,
+    policy: u32,
+    start_time: u64,
+    session_id: u32,
+    flags: u32,
+}
+
+fn read_process_stat(pid: &str) -> Result<ProcStatFields> {
     let line: String = std::fs::read_to_string(format!("/proc/{}/stat", pid)).context("reading /proc/PID/stat")?;
-    let parts: Vec<&str> = line.split_whitespace().collect();
-    if parts.len() < 14 {
+    // skip PID and command name, which is enclosed in parentheses and may contain spaces
+    let (_, rest) = line.rsplit_once(')').ok_or(anyhow!("command name not found"))?;
+    let parts: Vec<&str> = rest.split_whitespace().collect(); // starts from the 3rd field: state
+    if parts.len() < 39 {
         return Err(anyhow!("not enough parts"));
     }
-    let utime = parts[13].parse::<u64>().unwrap_or(0);
-    let stime = parts[14].parse::<u64>().unwrap_or(0);
-    Ok(utime + stime)
+    let utime = parts[11].parse::<u64>().unwrap_or(0);
+    let stime = parts[12].parse::<u64>().unwrap_or(0);
+    Ok(ProcStatFields {
+        cpu_ticks: utime + stime,
+        nice: parts[16].parse::<i32>().unwrap_or(0),
+        rt_priority: parts[37].parse::<u32>().unwrap_or(0),
+        policy: parts[38].parse::<u32>().unwrap_or(0),
+        start_time: parts[19].parse::<u64>().unwrap_or(0),
+        session_id: parts[3].parse::<u32>().unwrap_or(0),
+        flags: parts[6].parse::<u32>().unwrap_or(0),
+    })
+}
+
+/// Time the process started after system boot, in clock ticks. Tells apart processes that got the same PID.
+pub fn read_process_start_time(pid: &str) -> Result<u64> {
+    Ok(read_process_stat(pid)?.start_time)
 }
 
 fn read_cpu_load_avg(cpu_num: usize) -> Result<CpuLoadAvg> {
@@ -542,10 +747,11 @@ pub fn group_by_exe_path(processes: &Vec<ProcessStat>) -> Vec<ProcessStat> {
 pub fn merge_processes_group(processes: Vec<ProcessStat>) -> ProcessStat {
     let first = processes.get(0).unwrap();
     let cpu_usage: f64 = processes.iter().map(|p| p.cpu_usage).sum();
-    let memory_usage: f64 = processes.iter().map(|p| p.memory_usage).sum();
+    let pss_usage: f64 = processes.iter().map(|p| p.pss_usage).sum();
     let disk_usage: f64 = processes.iter().map(|p| p.disk_usage).sum();
     let cpu_time: f64 = processes.iter().map(|p| p.cpu_time).sum();
     let run_time: u64 = processes.iter().map(|p| p.run_time).max().unwrap_or(0);
+    let oom_score: i32 = processes.iter().map(|p| p.oom_score).max().unwrap_or(0);
     ProcessStat {
         pid: first.pid.clone(),
         pid_num: first.pid_num,
@@ -555,14 +761,33 @@ pub fn merge_processes_group(processes: Vec<ProcessStat>) -> ProcessStat {
         cwd: first.cwd.clone(),
         status: first.status.clone(),
         user_id: first.user_id,
+        user_name: first.user_name.clone(),
         display_name: first.exe.clone(),
         time_ms: first.time_ms,
         cpu_usage,
-        memory_usage,
+        memory_usage: pss_usage,
+        pss_usage,
+        memory: MemoryBreakdown::sum(processes.iter().filter_map(|p| p.memory.as_ref())),
         disk_usage,
+        disk_read_bytes: processes.iter().map(|p| p.disk_read_bytes).sum(),
+        disk_written_bytes: processes.iter().map(|p| p.disk_written_bytes).sum(),
+        disk_read_rate: processes.iter().map(|p| p.disk_read_rate).sum(),
+        disk_write_rate: processes.iter().map(|p| p.disk_write_rate).sum(),
         run_time,
         cpu_time,
         parent_pid: first.parent_pid.clone(),
+        nice: first.nice,
+        sched_policy: first.sched_policy,
+        rt_priority: first.rt_priority,
+        io_priority: first.io_priority,
+        oom_score,
+        oom_score_adj: first.oom_score_adj,
+        start_time: first.start_time,
+        session_id: first.session_id,
+        kernel_thread: first.kernel_thread,
+        protection: processes.iter().find_map(|p| p.protection.clone()),
+        systemd_unit: first.systemd_unit.clone(),
+        holds_path: processes.iter().flat_map(|p| p.holds_path.clone()).unique().collect(),
         group_children: processes,
     }
 }
@@ -0,0 +1,141 @@
+use anyhow::Result;
+
+use crate::action_menu::execute_shell_output;
+use crate::escalation::execute_privileged;
+use crate::strings::shell_quote;
+
+/// Systemd service, scope or slice the process belongs to, read from its cgroup
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemdUnit {
+    pub name: String,              // e.g. nginx.service, session-2.scope
+    pub user_manager: Option<u32>, // UID of the user's service manager, None for the system manager
+}
+
+impl SystemdUnit {
+    /// Services are usually restarted by systemd when their main process dies
+    pub fn is_service(&self) -> bool {
+        self.name.ends_with(".service")
+    }
+
+    pub fn describe(&self) -> String {
+        match self.user_manager {
+            Some(uid) => format!("{} (user {} manager)", self.name, uid),
+            None => format!("{} (system manager)", self.name),
+        }
+    }
+}
+
+pub fn read_systemd_unit(pid: &str) -> Option<SystemdUnit> {
+    let content = std::fs::read_to_string(format!("/proc/{}/cgroup", pid)).ok()?;
+    parse_cgroup_unit(&content)
+}
+
+/// Innermost unit from the unified hierarchy (`0::/system.slice/nginx.service`),
+/// or from the named systemd hierarchy of cgroup v1 (`1:name=systemd:/...`)
+fn parse_cgroup_unit(content: &str) -> Option<SystemdUnit> {
+    let path = content.lines().find_map(|line| {
+        let mut parts = line.splitn(3, ':');
+        let (_, controllers, path) = (parts.next()?, parts.next()?, parts.next()?);
+        match controllers {
+            "" | "name=systemd" => Some(path),
+            _ => None,
+        }
+    })?;
+    let components: Vec<&str> = path.split('/').filter(|it| !it.is_empty()).collect();
+    let unit_index = components
+        .iter()
+        .rposition(|it| it.ends_with(".service") || it.ends_with(".scope"))
+        .or_else(|| components.iter().rposition(|it| it.ends_with(".slice")))?;
+    let user_manager = components[..unit_index]
+        .iter()
+        .find_map(|it| it.strip_prefix("user@")?.strip_suffix(".service")?.parse::<u32>().ok());
+    Some(SystemdUnit {
+        name: components[unit_index].to_string(),
+        user_manager,
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnitCommand {
+    Stop,
+    Restart,
+    Kill, // signal all processes of the unit without stopping it
+}
+
+impl UnitCommand {
+    pub fn verb(&self) -> &'static str {
+        match self {
+            UnitCommand::Stop => "stop",
+            UnitCommand::Restart => "restart",
+            UnitCommand::Kill => "kill",
+        }
+    }
+}
+
+/// Run `systemctl` against the unit's manager, as Superuser for system units and other users' units
+pub fn run_unit_command(unit: &SystemdUnit, command: UnitCommand) -> Result<()> {
+    let (cmd, privileged) = unit_command_line(unit, command);
+    match privileged {
+        true => execute_privileged(cmd),
+        false => execute_shell_output(cmd).map(|_| ()),
+    }
+}
+
+/// Shell command controlling the unit, and whether it has to run as Superuser
+pub fn unit_command_line(unit: &SystemdUnit, command: UnitCommand) -> (String, bool) {
+    let euid = unsafe { libc::geteuid() };
+    let (manager_flags, privileged) = match unit.user_manager {
+        Some(uid) if uid == euid => ("--user".to_string(), false),
+        Some(uid) => (format!("--user --machine={}@.host", uid), euid != 0),
+        None => ("--system".to_string(), euid != 0),
+    };
+    let cmd = format!(
+        "systemctl {} {} -- {}",
+        manager_flags,
+        command.verb(),
+        shell_quote(&unit.name)
+    );
+    (cmd, privileged)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn unit(name: &str, user_manager: Option<u32>) -> Option<SystemdUnit> {
+        Some(SystemdUnit {
+            name: name.to_string(),
+            user_manager,
+        })
+    }
+
+    #[test]
+    fn test_parse_cgroup_unit() {
+        assert_eq!(
+            parse_cgroup_unit("0::/system.slice/nginx.service\n"),
+            unit("nginx.service", None)
+        );
+        assert_eq!(
+            parse_cgroup_unit("0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-firefox.scope\n"),
+            unit("app-firefox.scope", Some(1000))
+        );
+        assert_eq!(
+            parse_cgroup_unit("0::/user.slice/user-1000.slice/session-2.scope\n"),
+            unit("session-2.scope", None)
+        );
+        assert_eq!(
+            parse_cgroup_unit("0::/system.slice/docker-abc.scope/init.scope\n"),
+            unit("init.scope", None)
+        );
+        assert_eq!(parse_cgroup_unit("0::/machine.slice\n"), unit("machine.slice", None));
+        assert_eq!(parse_cgroup_unit("0::/\n"), None);
+        assert_eq!(parse_cgroup_unit("0::/docker/abc\n"), None);
+        assert_eq!(parse_cgroup_unit(""), None);
+    }
+
+    #[test]
+    fn test_parse_cgroup_v1_unit() {
+        let content = "12:memory:/system.slice/sshd.service\n1:name=systemd:/system.slice/sshd.service\n0::/\n";
+        assert_eq!(parse_cgroup_unit(content), unit("sshd.service", None));
+    }
+}
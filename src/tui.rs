@@ -56,10 +56,20 @@ impl Tui {
             Event::Tick => app.tick(),
             Event::Key(key_event) => update_on_key(app, key_event),
             Event::Resize => {}
+            Event::KillProgress(progress) => app.on_kill_progress(progress),
         };
         Ok(())
     }
 
+    /// Take over the terminal again after it was handed over to a subprocess with `exit`
+    pub fn resume(&mut self) -> Result<()> {
+        terminal::enable_raw_mode()?;
+        crossterm::execute!(io::stderr(), EnterAlternateScreen,)?;
+        self.event_handler.resume();
+        self.terminal.clear()?;
+        Ok(())
+    }
+
     fn fatal_exit() -> Result<()> {
         terminal::disable_raw_mode()?;
         crossterm::execute!(io::stderr(), LeaveAlternateScreen,)?;
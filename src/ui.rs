@@ -7,9 +7,13 @@ use ratatui::{
 
 use crate::action_menu::MenuAction;
 use crate::app::App;
-use crate::appdata::WindowFocus;
-use crate::numbers::{format_duration, ClampNumExt, MyIntExt, PercentFormatterExt};
-use crate::strings::apply_scroll;
+use crate::appdata::{ExtraColumn, TreeKillOrder, WindowFocus};
+use crate::escalation;
+use crate::numbers::{format_bytes, format_duration, ClampNumExt, MyIntExt, PercentFormatterExt};
+use crate::process_tree::format_tree;
+use crate::signals::{signal_name, SignalInfo};
+use crate::smaps::MemoryBreakdown;
+use crate::strings::{apply_scroll, truncate_ellipsis};
 use crate::sysinfo::ProcessStat;
 
 const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -30,6 +34,43 @@ pub fn render(app: &mut App, frame: &mut Frame) {
     if app.window_focus == WindowFocus::SignalPick {
         render_signal_panel(app, frame);
     }
+    if app.window_focus == WindowFocus::SignalCatalogue {
+        render_signal_catalogue_panel(app, frame);
+    }
+    if app.window_focus == WindowFocus::TreeKillPreview {
+        render_tree_kill_popup(app, frame);
+    }
+    if app.window_focus == WindowFocus::KillConfirm {
+        render_kill_confirm_popup(app, frame);
+    }
+    if app.window_focus == WindowFocus::OptionPick {
+        let labels: Vec<String> = app.option_pick_items.iter().map(|it| it.label.clone()).collect();
+        render_pick_list(frame, app.option_pick_title.clone(), labels, app.option_pick_cursor);
+    }
+    if app.window_focus == WindowFocus::AffinityPick {
+        render_affinity_pick(app, frame);
+    }
+    if app.window_focus == WindowFocus::PasswordPrompt {
+        render_password_popup(app, frame);
+    }
+    if app.window_focus == WindowFocus::ProtectedConfirm {
+        let message = format!(
+            "{}\n\n> {}\u{2588}",
+            app.protected_confirm_message, app.protected_confirm_input
+        );
+        let footer = "`Enter` to confirm, `Esc` to cancel".to_string();
+        render_confirmation_popup(frame, "Protected process".to_string(), message, 0, footer);
+    }
+    if app.window_focus == WindowFocus::ColumnPick {
+        let labels: Vec<String> = ExtraColumn::all()
+            .iter()
+            .map(|column| match app.extra_columns.contains(column) {
+                true => format!("[x] {}", column.name()),
+                false => format!("[ ] {}", column.name()),
+            })
+            .collect();
+        render_pick_list(frame, "Visible columns".to_string(), labels, app.column_pick_cursor);
+    }
     if app.info_message.is_some() {
         render_info_popup(app, frame);
     }
@@ -49,8 +90,11 @@ fn render_main_view(app: &mut App, frame: &mut Frame, area: Rect) {
     render_filter_panel(app, frame, layout[2]);
 }
 
-fn render_info_panel(_app: &mut App, frame: &mut Frame, area: Rect) {
-    let p_text = "`?` for controls. `Ctrl+F` to filter. `R` to refresh. `S` to sort. `Enter` to execute.";
+fn render_info_panel(app: &mut App, frame: &mut Frame, area: Rect) {
+    let p_text = match app.dry_run {
+        true => "DRY RUN: actions only show what they would do. `?` for controls. `Ctrl+F` to filter. `Enter` to plan.",
+        false => "`?` for controls. `Ctrl+F` to filter. `R` to refresh. `S` to sort. `Enter` to execute.",
+    };
     let widget = Paragraph::new(p_text)
         .wrap(Wrap { trim: true })
         .style(Style::default().fg(Color::White))
@@ -80,6 +124,9 @@ fn render_filter_panel(app: &mut App, frame: &mut Frame, area: Rect) {
     if app.window_focus == WindowFocus::ProcessFilter {
         title = title.title_style(Style::new().bold());
     }
+    if let Some(note) = &app.filter_note {
+        title = title.title_bottom(Line::from(note.as_str()).right_aligned());
+    }
 
     let widget = Paragraph::new(p_text)
         .block(
@@ -99,13 +146,32 @@ fn render_proc_list(app: &mut App, frame: &mut Frame, area: Rect) {
         .filtered_processes
         .iter()
         .map(|it: &ProcessStat| {
-            Row::new(vec![
+            let mut name = match it.is_stopped() {
+                true => format!("⏸ {}", apply_scroll(&it.display_name, app.horizontal_scroll)),
+                false => apply_scroll(&it.display_name, app.horizontal_scroll),
+            };
+            if it.protection.is_some() {
+                name = format!("🔒{}", name);
+            }
+            if !it.holds_path.is_empty() {
+                name = format!("{} [{}]", name, it.holds_path.join(", "));
+            }
+            let mut cells = vec![
                 it.pid.clone(),
-                apply_scroll(&it.display_name, app.horizontal_scroll),
+                name,
                 format_duration(it.run_time),
                 it.memory_usage.to_percent1(),
                 it.format_cpu_usage(),
-            ])
+            ];
+            cells.extend(app.extra_columns.iter().map(|column| extra_column_cell(column, it)));
+            let row = Row::new(cells);
+            if app.is_marked(it) {
+                row.style(Style::new().fg(Color::LightMagenta).bold())
+            } else if it.is_stopped() {
+                row.style(Style::new().fg(Color::LightBlue))
+            } else {
+                row
+            }
         })
         .collect();
     let col_pid_length: i32 = app
@@ -118,27 +184,49 @@ fn render_proc_list(app: &mut App, frame: &mut Frame, area: Rect) {
     let uptime_col_w = 9;
     let mem_col_w = 5;
     let cpu_col_w = 6;
-    let rest_width = (w - col_pid_length - uptime_col_w - mem_col_w - cpu_col_w - 4 - 2 - 2).clamp_min(3); // -4 for padding, -2 for cursor, -2 for borders
-    let widths = [
+    let extra_cols_w: i32 = app.extra_columns.iter().map(|it| extra_column_width(it) + 1).sum(); // +1 for padding
+    let rest_width =
+        (w - col_pid_length - uptime_col_w - mem_col_w - cpu_col_w - extra_cols_w - 4 - 2 - 2).clamp_min(3); // -4 for padding, -2 for cursor, -2 for borders
+    let mut widths = vec![
         Constraint::Length(col_pid_length as u16), // PID
         Constraint::Min(rest_width as u16),        // Name
         Constraint::Max(uptime_col_w as u16),      // Uptime
         Constraint::Max(mem_col_w as u16),         // MEM
         Constraint::Max(cpu_col_w as u16),         // CPU
     ];
-    let headers = match app.ordering {
+    widths.extend(
+        app.extra_columns
+            .iter()
+            .map(|it| Constraint::Max(extra_column_width(it) as u16)),
+    );
+    let mut headers: Vec<String> = match app.ordering {
         crate::appdata::Ordering::ByUptime => ["PID", "Name", "Uptime↓", "MEM", "CPU"],
         crate::appdata::Ordering::ByMemory => ["PID", "Name", "Uptime", "MEM↑", "CPU"],
         crate::appdata::Ordering::ByCpu => ["PID", "Name", "Uptime", "MEM", "CPU↑"],
-    };
+        _ => ["PID", "Name", "Uptime", "MEM", "CPU"],
+    }
+    .iter()
+    .map(|it| it.to_string())
+    .collect();
+    headers.extend(
+        app.extra_columns
+            .iter()
+            .map(|it| match it.ordering().as_ref() == Some(&app.ordering) {
+                true => format!("{}↑", extra_column_header(it)),
+                false => extra_column_header(it).to_string(),
+            }),
+    );
     let panel_color = match app.window_focus {
         WindowFocus::Browse => Color::LightYellow,
         _ => Color::White,
     };
-    let title = match app.group_by_exe {
-        false => "Running Processes",
-        true => "Running Processes (grouped by executable)",
+    let mut title = match app.group_by_exe {
+        false => "Running Processes".to_string(),
+        true => "Running Processes (grouped by executable)".to_string(),
     };
+    if !app.marked_pids.is_empty() {
+        title = format!("{} - {} marked", title, app.marked_pids.len());
+    }
     let mut title = Block::default().title(title);
     if app.window_focus == WindowFocus::Browse {
         title = title.title_style(Style::new().bold());
@@ -161,6 +249,63 @@ fn render_proc_list(app: &mut App, frame: &mut Frame, area: Rect) {
     frame.render_stateful_widget(table, area, &mut app.proc_list_table_state);
 }
 
+fn extra_column_header(column: &ExtraColumn) -> &'static str {
+    match column {
+        ExtraColumn::Priority => "PRI",
+        ExtraColumn::OomScore => "OOM",
+        ExtraColumn::Pss => "PSS",
+        ExtraColumn::Uss => "USS",
+        ExtraColumn::RssAnon => "ANON",
+        ExtraColumn::RssFile => "FILE",
+        ExtraColumn::Shmem => "SHMEM",
+        ExtraColumn::Swap => "SWAP",
+        ExtraColumn::DiskIo => "IO",
+        ExtraColumn::Unit => "UNIT",
+    }
+}
+
+fn extra_column_width(column: &ExtraColumn) -> i32 {
+    match column {
+        ExtraColumn::Priority => 6,
+        ExtraColumn::OomScore => 5,
+        ExtraColumn::Pss
+        | ExtraColumn::Uss
+        | ExtraColumn::RssAnon
+        | ExtraColumn::RssFile
+        | ExtraColumn::Shmem
+        | ExtraColumn::Swap => 10,
+        ExtraColumn::DiskIo => 12,
+        ExtraColumn::Unit => 24,
+    }
+}
+
+fn extra_column_cell(column: &ExtraColumn, process: &ProcessStat) -> String {
+    match column {
+        ExtraColumn::Priority => process.format_priority(),
+        ExtraColumn::OomScore => process.oom_score.to_string(),
+        ExtraColumn::Pss => format_memory_cell(process, |it| it.pss),
+        ExtraColumn::Uss => format_memory_cell(process, |it| it.uss),
+        ExtraColumn::RssAnon => format_memory_cell(process, |it| it.rss_anon),
+        ExtraColumn::RssFile => format_memory_cell(process, |it| it.rss_file),
+        ExtraColumn::Shmem => format_memory_cell(process, |it| it.shmem),
+        ExtraColumn::Swap => format_memory_cell(process, |it| it.swap),
+        ExtraColumn::DiskIo => process.format_disk_io_rate(),
+        ExtraColumn::Unit => process
+            .systemd_unit
+            .as_ref()
+            .map(|it| it.name.clone())
+            .unwrap_or("-".to_string()),
+    }
+}
+
+fn format_memory_cell(process: &ProcessStat, value: fn(&MemoryBreakdown) -> u64) -> String {
+    process
+        .memory
+        .as_ref()
+        .map(|it| format_bytes(value(it)))
+        .unwrap_or("-".to_string())
+}
+
 fn render_system_view(app: &mut App, frame: &mut Frame, area: Rect) {
     let panel_color = match app.window_focus {
         WindowFocus::SystemStats => Color::LightYellow,
@@ -185,16 +330,28 @@ fn render_system_view(app: &mut App, frame: &mut Frame, area: Rect) {
 }
 
 fn render_signal_panel(app: &mut App, frame: &mut Frame) {
+    // the list scrolls to the selected action when the menu doesn't fit
+    let height = (app.known_menu_actions.len() as u16 + 2).clamp_max(frame.area().height);
+    let width: u16 = (app
+        .known_menu_actions
+        .iter()
+        .map(|it: &MenuAction| it.name.chars().count() as u16)
+        .max()
+        .unwrap_or(0)
+        + 8)
+    .clamp_max(frame.area().width);
+    // long custom commands are cut to leave room for the borders and the highlight symbol
+    let label_width = width.saturating_sub(5) as usize;
     let list_items: Vec<ListItem> = app
         .known_menu_actions
         .iter()
-        .map(|it: &MenuAction| ListItem::new(it.name))
+        .map(|it: &MenuAction| ListItem::new(truncate_ellipsis(&it.name, label_width)))
         .collect();
     let mut list_state = ListState::default().with_selected(Some(app.menu_action_cursor));
     let widget = List::new(list_items)
         .block(
             Block::default()
-                .title("Choose a command")
+                .title(app.menu_title())
                 .borders(Borders::ALL)
                 .bg(Color::DarkGray),
         )
@@ -202,20 +359,143 @@ fn render_signal_panel(app: &mut App, frame: &mut Frame) {
         .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
         .highlight_symbol(">> ");
 
-    let height = app.known_menu_actions.len() as u16 + 2;
-    let width: u16 = app
-        .known_menu_actions
+    let area = centered_rect(width, height, frame.area());
+    let buffer = frame.buffer_mut();
+    Clear.render(area, buffer);
+    frame.render_stateful_widget(widget, area, &mut list_state);
+}
+
+fn render_signal_catalogue_panel(app: &mut App, frame: &mut Frame) {
+    let labels: Vec<String> = app
+        .signal_catalogue
+        .iter()
+        .map(|it: &SignalInfo| it.menu_label())
+        .collect();
+    let title = match app.signal_catalogue_superuser {
+        false => "Choose a signal",
+        true => "Choose a signal to send as Superuser",
+    };
+    render_pick_list(frame, title.to_string(), labels, app.signal_catalogue_cursor);
+}
+
+fn render_affinity_pick(app: &mut App, frame: &mut Frame) {
+    let checkbox = |checked: bool| match checked {
+        true => "[x]",
+        false => "[ ]",
+    };
+    let mut labels: Vec<String> = app
+        .affinity_cores
+        .iter()
+        .enumerate()
+        .map(|(core, allowed)| format!("{} CPU {}", checkbox(*allowed), core))
+        .collect();
+    labels.push(format!("{} Apply to all threads", checkbox(app.affinity_all_threads)));
+    labels.push(format!(
+        "{} Apply to all group members",
+        checkbox(app.affinity_group_members)
+    ));
+    let title = "CPU affinity: `Space` to toggle, `A` all, `Enter` to apply".to_string();
+    render_pick_list(frame, title, labels, app.affinity_cursor);
+}
+
+fn render_pick_list(frame: &mut Frame, title: String, labels: Vec<String>, cursor: usize) {
+    let list_items: Vec<ListItem> = labels.iter().map(|it| ListItem::new(it.as_str())).collect();
+    let mut list_state = ListState::default().with_selected(Some(cursor));
+    let height = (labels.len() as u16 + 2).clamp_max(frame.area().height);
+    let width: u16 = (labels
         .iter()
-        .map(|it: &MenuAction| it.name.len() as u16)
+        .map(|it| it.chars().count() as u16)
         .max()
         .unwrap_or(0)
-        + 8;
+        .max(title.chars().count() as u16)
+        + 8)
+    .clamp_max(frame.area().width);
+    let widget = List::new(list_items)
+        .block(Block::default().title(title).borders(Borders::ALL).bg(Color::DarkGray))
+        .style(Style::default().fg(Color::White).bg(Color::DarkGray))
+        .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+        .highlight_symbol(">> ");
+
     let area = centered_rect(width, height, frame.area());
-    let buffer = frame.buffer_mut();
-    Clear.render(area, buffer);
+    Clear.render(area, frame.buffer_mut());
     frame.render_stateful_widget(widget, area, &mut list_state);
 }
 
+fn render_tree_kill_popup(app: &mut App, frame: &mut Frame) {
+    let tree_text = format_tree(&app.tree_kill_nodes);
+    let mut title = format!(
+        "Process tree: {} processes to receive {}",
+        app.tree_kill_nodes.len(),
+        signal_name(app.tree_kill_signal)
+    );
+    if app.tree_kill_superuser {
+        title = format!("{} as Superuser", title);
+    }
+    let order = match app.tree_kill_order {
+        TreeKillOrder::ChildrenFirst => "children first",
+        TreeKillOrder::AllAtOnce => "all at once",
+    };
+    let footer = format!("`Enter` to send, `O` to change order ({}), `Esc` to cancel", order);
+    render_confirmation_popup(frame, title, tree_text, app.tree_kill_scroll, footer);
+}
+
+fn render_kill_confirm_popup(app: &mut App, frame: &mut Frame) {
+    render_confirmation_popup(
+        frame,
+        app.kill_confirm_title.clone(),
+        app.kill_confirm_message.clone(),
+        app.kill_confirm_scroll,
+        "`Enter` to confirm, `Esc` to cancel".to_string(),
+    );
+}
+
+fn render_password_popup(app: &mut App, frame: &mut Frame) {
+    let title = format!("Password for {}", escalation::backend().program());
+    let mut lines = vec![
+        "Authentication is required to run this action as Superuser.".to_string(),
+        String::new(),
+        format!("Password: {}\u{2588}", "•".repeat(app.password_input.chars().count())),
+    ];
+    if let Some(error) = &app.password_error {
+        lines.push(String::new());
+        lines.push(format!("Authentication failed: {}", error));
+    }
+    let footer = "`Enter` to authenticate, `Esc` to cancel".to_string();
+    render_confirmation_popup(frame, title, lines.join("\n"), 0, footer);
+}
+
+fn render_confirmation_popup(frame: &mut Frame, title: String, message: String, scroll: usize, footer: String) {
+    let display_message: String = message.lines().skip(scroll).collect::<Vec<&str>>().join("\n");
+    let width: u16 = frame.area().width.fraction(0.75);
+    let max_height: u16 = frame.area().height.fraction(0.75);
+    let text_height: u16 = message.lines().count().clamp_min(3).clamp_max(max_height.into()) as u16;
+    let title_block = Block::default()
+        .title(title)
+        .title_style(Style::new().bold())
+        .title_alignment(Alignment::Center)
+        .borders(Borders::ALL)
+        .bg(Color::DarkGray)
+        .padding(Padding::bottom(1))
+        .border_type(BorderType::Rounded);
+    let popup_window = Paragraph::new(Text::raw(display_message))
+        .block(title_block)
+        .style(Style::default().fg(Color::White));
+    let footer_label = Paragraph::new(footer)
+        .style(Style::default().bold().fg(Color::DarkGray).bg(Color::White))
+        .alignment(Alignment::Center);
+
+    let area = centered_rect(width, text_height + 3, frame.area());
+    let footer_area = Rect {
+        x: area.x + 1,
+        y: area.y + area.height - 2,
+        width: area.width - 2,
+        height: 1,
+    };
+    Clear.render(area, frame.buffer_mut());
+    frame.render_widget(popup_window, area);
+    frame.render_widget(footer_label, footer_area);
+}
+
 fn render_error_popup(app: &mut App, frame: &mut Frame) {
     if app.error_message.is_none() {
         return;
@@ -313,3 +593,72 @@ fn centered_rect(w: u16, h: u16, r: Rect) -> Rect {
         height: h,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    use super::*;
+    use crate::action_menu::{generate_known_menu_actions, ConfirmPolicy, Operation};
+    use crate::escalation::EscalationBackend;
+    use crate::signals::linux_signals;
+
+    fn draw_menu(app: &mut App, width: u16, height: u16) -> String {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|frame| render_signal_panel(app, frame)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| (0..width).map(|x| buffer[(x, y)].symbol()).collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_menu_scrolls_in_standard_terminal() {
+        let mut app = App {
+            known_menu_actions: generate_known_menu_actions(EscalationBackend::default()),
+            ..Default::default()
+        };
+        assert!(
+            app.known_menu_actions.len() > 22,
+            "menu should overflow 24 rows to test scrolling"
+        );
+        for (cursor, action) in app.known_menu_actions.clone().iter().enumerate() {
+            app.menu_action_cursor = cursor;
+            let screen = draw_menu(&mut app, 80, 24);
+            assert!(
+                screen.contains(&format!(">> {}", action.name)),
+                "{} is not visible",
+                action.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_menu_truncates_long_custom_commands() {
+        let mut app = App {
+            known_menu_actions: vec![MenuAction {
+                name: format!("Dump: {}", "x".repeat(200)),
+                operation: Operation::ShowDetails,
+                confirm: ConfirmPolicy::Never,
+            }],
+            ..Default::default()
+        };
+        let screen = draw_menu(&mut app, 80, 24);
+        assert!(screen.contains(">> Dump: xxx"));
+        assert!(screen.contains("x…│"));
+    }
+
+    #[test]
+    fn test_signal_catalogue_fits_narrow_terminal() {
+        let mut app = App {
+            signal_catalogue: linux_signals(),
+            ..Default::default()
+        };
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal
+            .draw(|frame| render_signal_catalogue_panel(&mut app, frame))
+            .unwrap();
+    }
+}